@@ -0,0 +1,44 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use reversi::board::{Board, Player};
+use reversi::heuristic::HeuristicEvaluator;
+use reversi::neural::NeuralNetwork;
+use reversi::pattern_weights::PatternWeights;
+
+fn fixture_board() -> Board {
+    Board::create_from_str(
+        "
+        - - - - - - - -
+        - - - - x - - -
+        - - o o x x - -
+        - - o o o x - -
+        - - - o x x - -
+        - - - o - - - -
+        - - - - - - - -
+        - - - - - - - -
+    ",
+    )
+}
+
+fn bench_evaluators(c: &mut Criterion) {
+    let board = fixture_board();
+
+    let weights = PatternWeights::default();
+    c.bench_function("evaluator::pattern", |b| {
+        b.iter(|| weights.score(black_box(&board), black_box(&Player::First)));
+    });
+
+    let heuristic = HeuristicEvaluator::default();
+    c.bench_function("evaluator::heuristic", |b| {
+        b.iter(|| heuristic.evaluate(black_box(&board), black_box(&Player::First)));
+    });
+
+    let network = NeuralNetwork::random(&[128, 32, 16, 1], 0).unwrap();
+    c.bench_function("evaluator::neural_network_128_32_16_1", |b| {
+        b.iter(|| network.evaluate(black_box(&board), black_box(&Player::First)));
+    });
+}
+
+criterion_group!(evaluator_benches, bench_evaluators);
+criterion_main!(evaluator_benches);
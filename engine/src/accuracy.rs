@@ -0,0 +1,280 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::board::Board;
+use crate::board::Player;
+
+/// Number of moves of each game stage in the report
+const STAGE_WIDTH: usize = 10;
+const N_STAGES: usize = 6;
+
+/// Position of a held-out game with the final disc difference of the player who has just moved,
+/// which is the target of the pattern evaluation (same as `python/learn.py`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestPosition {
+    /// Number of moves played so far, not counting passes
+    pub step: usize,
+    pub board: Board,
+    pub player: Player,
+    pub target: f32,
+}
+
+/// Test positions of a game from `positions` (e.g. `ggf::replay_positions`) and the final disc
+/// difference from the first player.
+pub fn test_positions(positions: &[(Board, Player)], final_score: f32) -> Vec<TestPosition> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i_step, (board, player))| TestPosition {
+            step: i_step + 1,
+            board: board.clone(),
+            player: player.clone(),
+            target: match player {
+                Player::First => final_score,
+                Player::Second => -final_score,
+            },
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageAccuracy {
+    pub steps: Range<usize>,
+    pub n_positions: usize,
+    /// Mean absolute error
+    pub mae: f32,
+    /// Pearson correlation between the predictions and the targets
+    pub correlation: f32,
+}
+
+impl StageAccuracy {
+    fn create(steps: Range<usize>, predictions: &[(f32, f32)]) -> StageAccuracy {
+        let n = predictions.len() as f32;
+        let mae = predictions.iter().map(|(p, t)| (p - t).abs()).sum::<f32>() / n.max(1.0);
+
+        let mean_p = predictions.iter().map(|(p, _)| p).sum::<f32>() / n;
+        let mean_t = predictions.iter().map(|(_, t)| t).sum::<f32>() / n;
+        let (mut cov, mut var_p, mut var_t) = (0.0, 0.0, 0.0);
+        for (p, t) in predictions {
+            cov += (p - mean_p) * (t - mean_t);
+            var_p += (p - mean_p) * (p - mean_p);
+            var_t += (t - mean_t) * (t - mean_t);
+        }
+        let correlation = if var_p > 0.0 && var_t > 0.0 {
+            cov / (var_p * var_t).sqrt()
+        } else {
+            0.0
+        };
+
+        StageAccuracy {
+            steps,
+            n_positions: predictions.len(),
+            mae,
+            correlation,
+        }
+    }
+}
+
+impl fmt::Display for StageAccuracy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>2}-{:<2} {:>8} {:>8.3} {:>8.3}",
+            self.steps.start,
+            self.steps.end - 1,
+            self.n_positions,
+            self.mae,
+            self.correlation
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PredictionError {
+    pub position: TestPosition,
+    pub prediction: f32,
+}
+
+impl fmt::Display for PredictionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "step {} ({:?} has just moved): prediction = {:.3}, final disc difference = {}",
+            self.position.step, self.position.player, self.prediction, self.position.target
+        )?;
+        write!(f, "{}", self.position.board.to_diagram())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccuracyReport {
+    pub overall: StageAccuracy,
+    /// Stages of `STAGE_WIDTH` moves, the empty stages are omitted
+    pub stages: Vec<StageAccuracy>,
+    /// Positions with the largest absolute errors, in descending order
+    pub largest_errors: Vec<PredictionError>,
+}
+
+impl AccuracyReport {
+    pub fn create(
+        evaluate: impl Fn(&Board, &Player) -> f32,
+        positions: &[TestPosition],
+        n_largest_errors: usize,
+    ) -> AccuracyReport {
+        let predictions: Vec<f32> = positions
+            .iter()
+            .map(|position| evaluate(&position.board, &position.player))
+            .collect();
+        let pairs: Vec<(f32, f32)> = predictions
+            .iter()
+            .zip(positions.iter())
+            .map(|(&prediction, position)| (prediction, position.target))
+            .collect();
+
+        let stages = (0..N_STAGES)
+            .map(|i_stage| {
+                let steps = (i_stage * STAGE_WIDTH + 1)..((i_stage + 1) * STAGE_WIDTH + 1);
+                let stage_pairs: Vec<(f32, f32)> = pairs
+                    .iter()
+                    .zip(positions.iter())
+                    .filter(|(_, position)| steps.contains(&position.step))
+                    .map(|(&pair, _)| pair)
+                    .collect();
+                StageAccuracy::create(steps, &stage_pairs)
+            })
+            .filter(|stage| stage.n_positions > 0)
+            .collect();
+
+        let mut errors: Vec<PredictionError> = positions
+            .iter()
+            .zip(predictions.iter())
+            .map(|(position, &prediction)| PredictionError {
+                position: position.clone(),
+                prediction,
+            })
+            .collect();
+        errors.sort_by(|a, b| {
+            let error_a = (a.prediction - a.position.target).abs();
+            let error_b = (b.prediction - b.position.target).abs();
+            error_b.total_cmp(&error_a)
+        });
+        errors.truncate(n_largest_errors);
+
+        AccuracyReport {
+            overall: StageAccuracy::create(1..(N_STAGES * STAGE_WIDTH + 1), &pairs),
+            stages,
+            largest_errors: errors,
+        }
+    }
+}
+
+impl fmt::Display for AccuracyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<5} {:>8} {:>8} {:>8}", "steps", "n", "MAE", "corr.")?;
+        for stage in &self.stages {
+            writeln!(f, "{}", stage)?;
+        }
+        writeln!(f, "{}", self.overall)?;
+        for (i, error) in self.largest_errors.iter().enumerate() {
+            writeln!(f, "\n#{} {}", i + 1, error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-stage comparison of two reports over the same positions. A negative MAE difference means
+/// that `b` is more accurate than `a`.
+pub fn render_comparison(a: &AccuracyReport, b: &AccuracyReport) -> String {
+    let mut lines = vec![format!(
+        "{:<5} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "steps", "MAE(a)", "MAE(b)", "diff", "corr(a)", "corr(b)"
+    )];
+    for (stage_a, stage_b) in a
+        .stages
+        .iter()
+        .chain([&a.overall])
+        .zip(b.stages.iter().chain([&b.overall]))
+    {
+        lines.push(format!(
+            "{:>2}-{:<2} {:>8.3} {:>8.3} {:>+8.3} {:>8.3} {:>8.3}",
+            stage_a.steps.start,
+            stage_a.steps.end - 1,
+            stage_a.mae,
+            stage_b.mae,
+            stage_b.mae - stage_a.mae,
+            stage_a.correlation,
+            stage_b.correlation
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::accuracy::*;
+
+    fn fixture_positions() -> Vec<TestPosition> {
+        let board = crate::board::new_board();
+        [(3, 10.0), (5, -4.0), (15, 20.0), (25, 0.0), (28, -6.0)]
+            .iter()
+            .map(|&(step, target)| TestPosition {
+                step,
+                board: board.clone(),
+                player: Player::First,
+                target,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_positions_have_the_target_of_the_mover() {
+        let board = crate::board::new_board();
+        let positions = test_positions(
+            &[(board.clone(), Player::First), (board, Player::Second)],
+            12.0,
+        );
+        assert_eq!(positions[0].step, 1);
+        assert_eq!(positions[0].target, 12.0);
+        assert_eq!(positions[1].target, -12.0);
+    }
+
+    #[test]
+    fn perfect_predictor() {
+        let positions = fixture_positions();
+        // the board is the same, so the target is looked up by the order of calls
+        let targets = std::cell::RefCell::new(positions.iter().map(|p| p.target));
+        let report = AccuracyReport::create(
+            |_: &Board, _: &Player| targets.borrow_mut().next().unwrap(),
+            &positions,
+            2,
+        );
+        assert_eq!(report.overall.mae, 0.0);
+        assert!((report.overall.correlation - 1.0).abs() < 1e-6);
+        assert_eq!(
+            report
+                .stages
+                .iter()
+                .map(|s| (s.steps.clone(), s.n_positions))
+                .collect::<Vec<(Range<usize>, usize)>>(),
+            vec![(1..11, 2), (11..21, 1), (21..31, 2)]
+        );
+    }
+
+    #[test]
+    fn constant_predictor() {
+        let positions = fixture_positions();
+        let report = AccuracyReport::create(|_: &Board, _: &Player| 1.0, &positions, 2);
+        assert_eq!(report.overall.mae, (9.0 + 5.0 + 19.0 + 1.0 + 7.0) / 5.0);
+        assert_eq!(report.overall.correlation, 0.0);
+        assert_eq!(report.stages[0].mae, 7.0);
+
+        assert_eq!(report.largest_errors.len(), 2);
+        assert_eq!(report.largest_errors[0].position.step, 15);
+        assert_eq!(report.largest_errors[1].position.step, 3);
+        assert!(report.to_string().contains("#1 step 15"));
+
+        let other = AccuracyReport::create(|_: &Board, _: &Player| 0.0, &positions, 0);
+        let comparison = render_comparison(&report, &other);
+        assert_eq!(comparison.lines().count(), 1 + 3 + 1);
+    }
+}
@@ -0,0 +1,668 @@
+pub mod sprt;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::board;
+use crate::board::bitboard;
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::book::Book;
+use crate::ggf;
+use crate::opening;
+use crate::rng::Rng;
+use crate::strategy::config;
+use crate::strategy::config::StrategyConfig;
+use crate::strategy::error::StrategyError;
+use crate::strategy::Strategy;
+use crate::win_probability;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    /// Moves from the initial position, `None` means pass
+    pub moves: Vec<Option<u64>>,
+    pub board: Board,
+}
+
+impl GameRecord {
+    /// Final disc difference from the first player (black)
+    pub fn score(&self) -> f32 {
+        self.board.score_numdisk(&Player::First)
+    }
+
+    /// GGF game with the names of the players of black and white
+    pub fn to_ggf(&self, first_name: &str, second_name: &str) -> ggf::Game {
+        let mut turn = 'B';
+        let moves = self
+            .moves
+            .iter()
+            .map(|m| {
+                let coord: Vec<char> = match m.and_then(Square::from_bitboard) {
+                    Some(square) => square.to_string().to_ascii_uppercase().chars().collect(),
+                    None => vec!['*', '*'],
+                };
+                let triple = (turn, coord[0], coord[1]);
+                turn = if turn == 'B' { 'W' } else { 'B' };
+                triple
+            })
+            .collect();
+        ggf::new_game(
+            "Othello",
+            "",
+            "",
+            first_name,
+            second_name,
+            0.0,
+            0.0,
+            "8",
+            self.score() as f64,
+            "8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *",
+            moves,
+        )
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let moves = self
+            .moves
+            .iter()
+            .map(|m| bitboard::put_position_to_coord(*m).unwrap_or("??".to_string()))
+            .collect::<Vec<String>>()
+            .join("");
+        write!(f, "{} {:+}", moves, self.score())
+    }
+}
+
+/// Play a game from the initial position followed by `opening` between two strategies.
+pub fn play_game(
+    first: &mut dyn Strategy,
+    second: &mut dyn Strategy,
+    opening: &[u64],
+) -> Result<GameRecord, String> {
+    first.new_game();
+    second.new_game();
+    let mut board = board::new_board();
+    let mut player = Player::First;
+    let mut moves = Vec::new();
+
+    for &opening_move in opening {
+        if board.legal_moves(&player) & opening_move == 0 {
+            return Err(format!(
+                "illegal opening move {:?}",
+                bitboard::put_position_to_coord(Some(opening_move))
+            ));
+        }
+        board.put_and_reverse(&player, opening_move);
+        moves.push(Some(opening_move));
+        player = player.opponent();
+    }
+
+    loop {
+        if board.legal_moves(&player) == 0 {
+            if board.legal_moves(&player.opponent()) == 0 {
+                break;
+            }
+            moves.push(None);
+            player = player.opponent();
+            continue;
+        }
+
+        let i_step = moves.iter().filter(|m| m.is_some()).count();
+        let search_result = match player {
+            Player::First => first.get_next_move(&board, &player, i_step),
+            Player::Second => second.get_next_move(&board, &player, i_step),
+        };
+        let next_move = match search_result?.best_move {
+            Some(square) if board.legal_moves(&player) & square.bitboard() != 0 => {
+                square.bitboard()
+            }
+            Some(square) => {
+                return Err(format!(
+                    "{:?}: {}",
+                    player,
+                    StrategyError::IllegalMove(square)
+                ))
+            }
+            None => return Err(format!("{:?} passed with legal moves", player)),
+        };
+        board.put_and_reverse(&player, next_move);
+        moves.push(Some(next_move));
+        player = player.opponent();
+    }
+
+    Ok(GameRecord { moves, board })
+}
+
+/// Random sequence of legal moves from the initial position, used to diversify games between
+/// deterministic strategies.
+pub fn random_opening(rng: &mut Rng, n_moves: usize) -> Vec<u64> {
+    let mut board = board::new_board();
+    let mut player = Player::First;
+    let mut opening = Vec::new();
+    for _ in 0..n_moves {
+        let legal_moves = board.get_all_legal_moves(&player);
+        if legal_moves.is_empty() {
+            break;
+        }
+        let next_move = legal_moves[rng.next_usize(legal_moves.len())];
+        board.put_and_reverse(&player, next_move);
+        opening.push(next_move);
+        player = player.opponent();
+    }
+    opening
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub disc_difference_sum: f32,
+    /// Numbers of the pairs of games with swapped colors (see `add_pair`) by the points of the
+    /// first strategy in both games: 0, 0.5, 1, 1.5 and 2
+    pub pair_points: [usize; 5],
+}
+
+impl MatchResult {
+    pub fn n_games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn n_pairs(&self) -> usize {
+        self.pair_points.iter().sum()
+    }
+
+    /// Record a final disc difference from the viewpoint of the first strategy of the match
+    pub fn add(&mut self, disc_difference: f32) {
+        if disc_difference > 0.0 {
+            self.wins += 1;
+        } else if disc_difference < 0.0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+        self.disc_difference_sum += disc_difference;
+    }
+
+    /// Record the games of an opening played with both colors, whose final disc differences are
+    /// from the viewpoint of the first strategy of the match
+    pub fn add_pair(&mut self, first_disc_difference: f32, second_disc_difference: f32) {
+        self.add(first_disc_difference);
+        self.add(second_disc_difference);
+        let points = win_probability::outcome(first_disc_difference)
+            + win_probability::outcome(second_disc_difference);
+        self.pair_points[(2.0 * points) as usize] += 1;
+    }
+
+    /// Average points per game, where a draw is half a point
+    pub fn score_rate(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.n_games().max(1) as f64
+    }
+
+    /// Elo difference of the first strategy and the half width of its 95% confidence interval,
+    /// from the standard error of the points per game by the delta method. Infinite when all
+    /// the games are won or lost.
+    ///
+    /// The games of a pair share their opening and are not independent, so the variance is of
+    /// the points of the pairs when all the games are paired. Otherwise it is of the points of
+    /// the games, which is optimistic for paired games.
+    pub fn elo_difference(&self) -> (f64, f64) {
+        let rate = self.score_rate();
+        let elo = -400.0 * (1.0 / rate - 1.0).log10();
+        if !elo.is_finite() {
+            return (elo, f64::INFINITY);
+        }
+        let n_pairs = self.n_pairs();
+        let (variance, n_samples) = if n_pairs > 0 && 2 * n_pairs == self.n_games() {
+            // average points per game of each pair
+            let variance = (0..5)
+                .map(|i| self.pair_points[i] as f64 * (i as f64 / 4.0 - rate).powi(2))
+                .sum::<f64>()
+                / n_pairs as f64;
+            (variance, n_pairs as f64)
+        } else {
+            let n_games = self.n_games().max(1) as f64;
+            let variance = (self.wins as f64 * (1.0 - rate).powi(2)
+                + self.draws as f64 * (0.5 - rate).powi(2)
+                + self.losses as f64 * rate.powi(2))
+                / n_games;
+            (variance, n_games)
+        };
+        // derivative of the Elo difference by the rate
+        let slope = 400.0 / (std::f64::consts::LN_10 * rate * (1.0 - rate));
+        (elo, 1.96 * slope * (variance / n_samples).sqrt())
+    }
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} (average disc difference {:+.2})",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.disc_difference_sum / self.n_games().max(1) as f32
+        )
+    }
+}
+
+/// Creates the strategies of the games of each opening of `run_match` from the index of the
+/// opening, e.g. to seed their random numbers
+pub type StrategyFactory<'a> = dyn Fn(usize) -> Result<Box<dyn Strategy>, String> + Sync + 'a;
+
+/// Called by `run_match_with_callback` with the index of each opening and its games as soon as
+/// they are finished, `a` playing black first
+pub type PairCallback<'a> =
+    dyn Fn(usize, &GameRecord, &GameRecord) -> Result<(), String> + Sync + 'a;
+
+/// `play_match` on `n_threads` threads. The strategies are created for each opening, so that the
+/// games do not depend on which thread plays them. The games are returned in the order of
+/// `openings`, `a` playing black first.
+pub fn run_match(
+    create_a: &StrategyFactory,
+    create_b: &StrategyFactory,
+    openings: &[Vec<u64>],
+    n_threads: usize,
+) -> Result<(MatchResult, Vec<GameRecord>), String> {
+    run_match_with_callback(create_a, create_b, openings, n_threads, &|_, _, _| Ok(()))
+}
+
+/// `run_match` which gives the games of each opening to `on_pair` as soon as they are finished,
+/// e.g. to save them before the match is interrupted. An error of `on_pair` stops the match.
+pub fn run_match_with_callback(
+    create_a: &StrategyFactory,
+    create_b: &StrategyFactory,
+    openings: &[Vec<u64>],
+    n_threads: usize,
+    on_pair: &PairCallback,
+) -> Result<(MatchResult, Vec<GameRecord>), String> {
+    let next_opening = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let games = Mutex::new(Vec::new());
+    let play = || -> Result<(), String> {
+        loop {
+            let i_opening = next_opening.fetch_add(1, Ordering::Relaxed);
+            if i_opening >= openings.len() || failed.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let mut a = create_a(i_opening)?;
+            let mut b = create_b(i_opening)?;
+            let opening = &openings[i_opening];
+            let a_first = play_game(a.as_mut(), b.as_mut(), opening)?;
+            let b_first = play_game(b.as_mut(), a.as_mut(), opening)?;
+            on_pair(i_opening, &a_first, &b_first)?;
+            games.lock().unwrap().push((i_opening, a_first, b_first));
+        }
+    };
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..n_threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let result = play();
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    result
+                })
+            })
+            .collect();
+        handles.into_iter().try_for_each(|handle| {
+            handle
+                .join()
+                .map_err(|_| String::from("match thread panicked"))?
+        })
+    })?;
+
+    let mut games = games.into_inner().unwrap();
+    games.sort_by_key(|(i_opening, _, _)| *i_opening);
+    let mut result = MatchResult::default();
+    let mut records = Vec::new();
+    for (_, a_first, b_first) in games {
+        result.add_pair(a_first.score(), -b_first.score());
+        records.push(a_first);
+        records.push(b_first);
+    }
+    Ok((result, records))
+}
+
+/// A random opening of `n_moves` moves if its position is not in `keys`, which is distinct up to
+/// symmetry, and its score by `evaluator` is at most `max_abs_score`
+fn try_balanced_opening(
+    rng: &mut Rng,
+    n_moves: usize,
+    evaluator: &mut dyn Strategy,
+    max_abs_score: f32,
+    keys: &mut HashSet<(u64, u64)>,
+) -> Result<Option<Vec<u64>>, StrategyError> {
+    let opening = random_opening(rng, n_moves);
+    let Some((board, player)) = opening::replay_moves(&opening).pop() else {
+        return Ok(None);
+    };
+    let next_player = Book::next_player(&board, &player);
+    if board.legal_moves(&next_player) == 0 || !keys.insert(Book::key(&board, &next_player)) {
+        return Ok(None);
+    }
+    let result = evaluator.get_next_move(&board, &next_player, opening.len())?;
+    Ok((result.score.abs() <= max_abs_score).then_some(opening))
+}
+
+/// `n_openings` random openings of `n_moves` moves, distinct up to symmetry, whose score by
+/// `evaluator` is at most `max_abs_score` so that neither color is favored. Fewer openings are
+/// returned when not enough are found.
+pub fn balanced_openings(
+    rng: &mut Rng,
+    n_openings: usize,
+    n_moves: usize,
+    evaluator: &mut dyn Strategy,
+    max_abs_score: f32,
+) -> Result<Vec<Vec<u64>>, StrategyError> {
+    let mut openings = Vec::new();
+    let mut keys = HashSet::new();
+    for _ in 0..100 * n_openings {
+        if openings.len() >= n_openings {
+            break;
+        }
+        if let Some(opening) =
+            try_balanced_opening(rng, n_moves, evaluator, max_abs_score, &mut keys)?
+        {
+            openings.push(opening);
+        }
+    }
+    Ok(openings)
+}
+
+/// `balanced_openings` generated batch by batch, e.g. until a sequential test stops. The batches
+/// are the same openings as `balanced_openings` of their total number.
+pub struct BalancedOpenings {
+    rng: Rng,
+    n_moves: usize,
+    evaluator: Box<dyn Strategy>,
+    max_abs_score: f32,
+    keys: HashSet<(u64, u64)>,
+}
+
+impl BalancedOpenings {
+    pub fn create(
+        rng: Rng,
+        n_moves: usize,
+        evaluator: Box<dyn Strategy>,
+        max_abs_score: f32,
+    ) -> BalancedOpenings {
+        BalancedOpenings {
+            rng,
+            n_moves,
+            evaluator,
+            max_abs_score,
+            keys: HashSet::new(),
+        }
+    }
+
+    /// Openings of 8 moves evaluated by a 6-ply search with the pattern evaluator (see
+    /// `standard_openings`)
+    pub fn standard() -> Result<BalancedOpenings, String> {
+        let evaluator = config::build_strategy(
+            StrategyConfig {
+                depth_schedule: vec![(0, 6)],
+                evaluator: config::EvaluatorType::Pattern,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        )?;
+        Ok(BalancedOpenings::create(Rng::create(0), 8, evaluator, 4.0))
+    }
+
+    /// The next `n_openings` openings, or fewer when not enough are found
+    pub fn next_batch(&mut self, n_openings: usize) -> Result<Vec<Vec<u64>>, StrategyError> {
+        let mut openings = Vec::new();
+        for _ in 0..100 * n_openings {
+            if openings.len() >= n_openings {
+                break;
+            }
+            if let Some(opening) = try_balanced_opening(
+                &mut self.rng,
+                self.n_moves,
+                self.evaluator.as_mut(),
+                self.max_abs_score,
+                &mut self.keys,
+            )? {
+                openings.push(opening);
+            }
+        }
+        Ok(openings)
+    }
+}
+
+/// The first `n_openings` of `BalancedOpenings::standard`. The same openings are returned for the
+/// same `n_openings`.
+pub fn standard_openings(n_openings: usize) -> Result<Vec<Vec<u64>>, String> {
+    Ok(BalancedOpenings::standard()?.next_batch(n_openings)?)
+}
+
+/// Play each opening twice with alternating colors, and return the result of strategy `a`.
+pub fn play_match(
+    a: &mut dyn Strategy,
+    b: &mut dyn Strategy,
+    openings: &[Vec<u64>],
+) -> Result<MatchResult, String> {
+    let mut result = MatchResult::default();
+    for opening in openings {
+        let a_first = play_game(a, b, opening)?;
+        let b_first = play_game(b, a, opening)?;
+        result.add_pair(a_first.score(), -b_first.score());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::*;
+    use crate::test_utils::numdisk_strategy;
+
+    #[test]
+    fn play_game_until_the_end() {
+        let mut first = numdisk_strategy(1);
+        let mut second = numdisk_strategy(1);
+        let record = play_game(&mut first, &mut second, &[]).unwrap();
+
+        assert!(record.board.legal_moves(&Player::First) == 0);
+        assert!(record.board.legal_moves(&Player::Second) == 0);
+        let n_moves = record.moves.iter().filter(|m| m.is_some()).count() as u64;
+        let n_discs = board::count_bits(record.board.first() | record.board.second());
+        assert_eq!(n_moves + 4, n_discs);
+    }
+
+    #[test]
+    fn play_game_rejects_illegal_opening() {
+        let mut first = numdisk_strategy(1);
+        let mut second = numdisk_strategy(1);
+        assert!(play_game(&mut first, &mut second, &[1]).is_err());
+    }
+
+    #[test]
+    fn random_opening_is_reproducible() {
+        let opening1 = random_opening(&mut Rng::create(3), 6);
+        let opening2 = random_opening(&mut Rng::create(3), 6);
+        assert_eq!(opening1.len(), 6);
+        assert_eq!(opening1, opening2);
+    }
+
+    #[test]
+    fn play_match_same_strategies() {
+        let mut a = numdisk_strategy(1);
+        let mut b = numdisk_strategy(1);
+        let openings = vec![random_opening(&mut Rng::create(0), 4)];
+        let result = play_match(&mut a, &mut b, &openings).unwrap();
+        assert_eq!(result.n_games(), 2);
+        // the same game is played with swapped colors
+        assert_eq!(result.disc_difference_sum, 0.0);
+    }
+
+    #[test]
+    fn run_match_on_threads() {
+        let openings: Vec<Vec<u64>> = (0..3)
+            .map(|i| random_opening(&mut Rng::create(i), 4))
+            .collect();
+        let create_a =
+            |_| -> Result<Box<dyn Strategy>, String> { Ok(Box::new(numdisk_strategy(1))) };
+        let create_b =
+            |_| -> Result<Box<dyn Strategy>, String> { Ok(Box::new(numdisk_strategy(2))) };
+        let (result, records) = run_match(&create_a, &create_b, &openings, 2).unwrap();
+        let expected = play_match(
+            &mut numdisk_strategy(1),
+            &mut numdisk_strategy(2),
+            &openings,
+        );
+        assert_eq!(Ok(result), expected);
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[2].moves[..4], records[3].moves[..4]);
+    }
+
+    #[test]
+    fn run_match_with_callback_of_each_pair() {
+        let openings: Vec<Vec<u64>> = (0..3)
+            .map(|i| random_opening(&mut Rng::create(i), 4))
+            .collect();
+        let create = |_| -> Result<Box<dyn Strategy>, String> { Ok(Box::new(numdisk_strategy(1))) };
+        let pairs = Mutex::new(Vec::new());
+        let on_pair = |i_opening, a_first: &GameRecord, b_first: &GameRecord| {
+            pairs
+                .lock()
+                .unwrap()
+                .push((i_opening, a_first.clone(), b_first.clone()));
+            Ok(())
+        };
+        let (_, records) =
+            run_match_with_callback(&create, &create, &openings, 2, &on_pair).unwrap();
+        let mut pairs = pairs.into_inner().unwrap();
+        pairs.sort_by_key(|(i_opening, _, _)| *i_opening);
+        let paired_records: Vec<GameRecord> = pairs
+            .into_iter()
+            .flat_map(|(_, a_first, b_first)| [a_first, b_first])
+            .collect();
+        assert_eq!(paired_records, records);
+
+        // an error of the callback stops the match
+        let on_pair = |_, _: &GameRecord, _: &GameRecord| Err(String::from("disk full"));
+        assert_eq!(
+            run_match_with_callback(&create, &create, &openings, 2, &on_pair),
+            Err(String::from("disk full"))
+        );
+    }
+
+    #[test]
+    fn run_match_seeded_by_opening() {
+        let openings: Vec<Vec<u64>> = (0..4)
+            .map(|i| random_opening(&mut Rng::create(i), 4))
+            .collect();
+        // random moves half of the time
+        let config = StrategyConfig {
+            depth_schedule: vec![(0, 1)],
+            error_rate: 0.5,
+            ..StrategyConfig::default()
+        };
+        let create =
+            |i_opening| config::build_strategy(config.clone(), Rng::create(i_opening as u64));
+        let (result, records) = run_match(&create, &create, &openings, 1).unwrap();
+        for n_threads in [2, 4] {
+            assert_eq!(
+                run_match(&create, &create, &openings, n_threads).unwrap(),
+                (result.clone(), records.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn elo_difference() {
+        let result = MatchResult {
+            wins: 60,
+            draws: 30,
+            losses: 10,
+            ..MatchResult::default()
+        };
+        assert_eq!(result.score_rate(), 0.75);
+        let (elo, margin) = result.elo_difference();
+        assert!((elo - 400.0 * 3f64.log10()).abs() < 1e-9);
+        assert!(margin > 30.0 && margin < 100.0);
+
+        let even = MatchResult {
+            wins: 5,
+            draws: 0,
+            losses: 5,
+            ..MatchResult::default()
+        };
+        assert_eq!(even.elo_difference().0, 0.0);
+        let all_wins = MatchResult {
+            wins: 5,
+            ..MatchResult::default()
+        };
+        assert_eq!(all_wins.elo_difference().0, f64::INFINITY);
+    }
+
+    #[test]
+    fn elo_difference_of_pairs() {
+        // each opening is won by the side which plays black, so the pairs are all even
+        let mut result = MatchResult::default();
+        for _ in 0..10 {
+            result.add_pair(10.0, -10.0);
+        }
+        assert_eq!((result.n_games(), result.n_pairs()), (20, 10));
+        assert_eq!(result.pair_points, [0, 0, 10, 0, 0]);
+        assert_eq!(result.elo_difference(), (0.0, 0.0));
+
+        // the games of the pairs are counted as well as their points
+        result.add_pair(2.0, 0.0);
+        assert_eq!((result.wins, result.draws, result.losses), (11, 1, 10));
+        assert_eq!(result.pair_points, [0, 0, 10, 1, 0]);
+        let (elo, margin) = result.elo_difference();
+        assert!(elo > 0.0 && margin > 0.0);
+
+        // the games are treated as independent when some of them are not paired
+        let mut unpaired = result.clone();
+        unpaired.add(0.0);
+        assert_eq!(unpaired.n_pairs() * 2 + 1, unpaired.n_games());
+        assert!(unpaired.elo_difference().1 > margin);
+    }
+
+    #[test]
+    fn balanced_openings_in_batches() {
+        let mut generator =
+            BalancedOpenings::create(Rng::create(0), 6, Box::new(numdisk_strategy(2)), 4.0);
+        let mut batches = generator.next_batch(2).unwrap();
+        batches.extend(generator.next_batch(3).unwrap());
+        let openings =
+            balanced_openings(&mut Rng::create(0), 5, 6, &mut numdisk_strategy(2), 4.0).unwrap();
+        assert_eq!(batches, openings);
+    }
+
+    #[test]
+    fn balanced_openings_are_distinct() {
+        let mut evaluator = numdisk_strategy(2);
+        let openings = balanced_openings(&mut Rng::create(0), 5, 6, &mut evaluator, 4.0).unwrap();
+        assert_eq!(openings.len(), 5);
+        let keys: std::collections::HashSet<_> = openings
+            .iter()
+            .map(|opening| {
+                let (board, player) = opening::replay_moves(opening).pop().unwrap();
+                Book::key(&board, &player)
+            })
+            .collect();
+        assert_eq!(keys.len(), 5);
+    }
+
+    #[test]
+    fn game_record_to_ggf() {
+        let mut first = numdisk_strategy(1);
+        let mut second = numdisk_strategy(1);
+        let record = play_game(&mut first, &mut second, &[]).unwrap();
+        let game = ggf::parse(record.to_ggf("a", "b").to_string());
+        assert_eq!(game.result_score, record.score() as f64);
+        let (board, _) = ggf::replay_positions(&game).pop().unwrap();
+        assert_eq!(board, record.board);
+    }
+}
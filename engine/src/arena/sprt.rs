@@ -0,0 +1,174 @@
+use crate::arena::MatchResult;
+
+/// Expected points per game of a player `elo` stronger than its opponent
+fn elo_to_score_rate(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtDecision {
+    /// H1: the candidate is `elo1` stronger than the base
+    Accept,
+    /// H0: the candidate is `elo0` stronger than the base
+    Reject,
+    Continue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SprtConfig {
+    /// Elo differences of the null and alternative hypotheses
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probabilities of accepting H1 when H0 holds and of rejecting H1 when H1 holds
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for SprtConfig {
+    fn default() -> SprtConfig {
+        SprtConfig {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl SprtConfig {
+    /// Lower and upper bounds of the log-likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of H1 to H0 for the result of the candidate, by the normal
+    /// approximation of the points per game (win, draw or loss). 0 while the points have no
+    /// variance, e.g. only draws have been played.
+    pub fn llr(&self, result: &MatchResult) -> f64 {
+        let n_games = result.n_games() as f64;
+        if n_games == 0.0 {
+            return 0.0;
+        }
+        let rate = result.score_rate();
+        let variance = (result.wins as f64 * (1.0 - rate).powi(2)
+            + result.draws as f64 * (0.5 - rate).powi(2)
+            + result.losses as f64 * rate.powi(2))
+            / n_games;
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (elo_to_score_rate(self.elo0), elo_to_score_rate(self.elo1));
+        n_games * (s1 - s0) * (2.0 * rate - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn decision(&self, llr: f64) -> SprtDecision {
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtDecision::Accept
+        } else if llr <= lower {
+            SprtDecision::Reject
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+/// Sequential probability ratio test of a candidate against a base, updated with the results
+/// of batches of games until a decision is made
+pub struct Sprt {
+    config: SprtConfig,
+    result: MatchResult,
+    /// (number of games, LLR) after each batch
+    trajectory: Vec<(usize, f64)>,
+}
+
+impl Sprt {
+    pub fn create(config: SprtConfig) -> Sprt {
+        Sprt {
+            config,
+            result: MatchResult::default(),
+            trajectory: Vec::new(),
+        }
+    }
+
+    /// Add the result of the candidate in a batch of games
+    pub fn update(&mut self, batch: &MatchResult) -> SprtDecision {
+        self.result.wins += batch.wins;
+        self.result.draws += batch.draws;
+        self.result.losses += batch.losses;
+        self.result.disc_difference_sum += batch.disc_difference_sum;
+        for (pairs, batch_pairs) in self.result.pair_points.iter_mut().zip(batch.pair_points) {
+            *pairs += batch_pairs;
+        }
+        let llr = self.config.llr(&self.result);
+        self.trajectory.push((self.result.n_games(), llr));
+        self.config.decision(llr)
+    }
+
+    pub fn result(&self) -> &MatchResult {
+        &self.result
+    }
+
+    pub fn trajectory(&self) -> &[(usize, f64)] {
+        &self.trajectory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::sprt::*;
+
+    fn match_result(wins: usize, draws: usize, losses: usize) -> MatchResult {
+        MatchResult {
+            wins,
+            draws,
+            losses,
+            ..MatchResult::default()
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let (lower, upper) = SprtConfig::default().bounds();
+        assert!((lower + 2.944).abs() < 1e-3);
+        assert!((upper - 2.944).abs() < 1e-3);
+        assert_eq!(elo_to_score_rate(0.0), 0.5);
+    }
+
+    #[test]
+    fn llr() {
+        let config = SprtConfig::default();
+        assert_eq!(config.llr(&MatchResult::default()), 0.0);
+        assert_eq!(config.llr(&match_result(0, 10, 0)), 0.0);
+        // the score rate at the middle of the hypotheses
+        let (s0, s1) = (elo_to_score_rate(0.0), elo_to_score_rate(5.0));
+        assert!(((s0 + s1) / 2.0 - 0.5036).abs() < 1e-4);
+        assert!(config.llr(&match_result(50, 0, 50)) < 0.0);
+        assert!(config.llr(&match_result(60, 0, 40)) > 0.0);
+        // the LLR grows with the number of games for the same rate
+        assert!(config.llr(&match_result(600, 0, 400)) > config.llr(&match_result(60, 0, 40)));
+    }
+
+    #[test]
+    fn stops_at_bounds() {
+        let mut sprt = Sprt::create(SprtConfig::default());
+        assert_eq!(sprt.update(&match_result(6, 2, 4)), SprtDecision::Continue);
+        let mut decision = SprtDecision::Continue;
+        while decision == SprtDecision::Continue {
+            decision = sprt.update(&match_result(6, 2, 4));
+        }
+        assert_eq!(decision, SprtDecision::Accept);
+        assert_eq!(sprt.result().n_games(), 12 * sprt.trajectory().len());
+        assert!(sprt.trajectory().last().unwrap().1 >= SprtConfig::default().bounds().1);
+
+        let mut sprt = Sprt::create(SprtConfig::default());
+        let mut decision = SprtDecision::Continue;
+        while decision == SprtDecision::Continue {
+            decision = sprt.update(&match_result(4, 2, 6));
+        }
+        assert_eq!(decision, SprtDecision::Reject);
+    }
+}
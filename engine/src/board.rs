@@ -1,14 +1,27 @@
 pub mod bitboard;
 pub mod board_reverse;
+pub mod pattern_set;
 mod player;
+pub mod region;
+mod square;
+pub mod stability;
+pub mod symmetry;
 
 pub use player::Player;
+pub use square::Square;
 
 use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 
+#[cfg(not(feature = "quantized-weights"))]
 use crate::parameters::parameters::PATTERN_INSTANCES;
+#[cfg(feature = "quantized-weights")]
+use crate::parameters_quantized::parameters_quantized::{
+    PATTERN_INSTANCES_QUANTIZED, PATTERN_SCALE,
+};
+use crate::pattern_weights;
+use crate::pattern_weights::PatternWeights;
 use crate::utils;
 
 #[wasm_bindgen]
@@ -49,6 +62,12 @@ impl Board {
             .collect();
         convert_vec_to_jsarray(legal_positions)
     }
+
+    #[wasm_bindgen(js_name = explainPatternScore)]
+    pub fn explain_pattern_score(&self, player: Player) -> String {
+        let contributions = PatternWeights::default().explain(self, &player);
+        pattern_weights::render_explanation(&contributions)
+    }
 }
 
 impl Board {
@@ -124,7 +143,7 @@ impl Board {
         }
     }
 
-    fn transfer_board(board: u64, direction: u8) -> u64 {
+    pub(crate) fn transfer_board(board: u64, direction: u8) -> u64 {
         match direction {
             0 => {
                 // right
@@ -186,16 +205,55 @@ impl Board {
         reverse_patterns
     }
 
+    /// Bitboard of all legal moves of `player`, computed by shifting along the 8 directions.
+    pub fn legal_moves(&self, player: &Player) -> u64 {
+        let (current, opponent) = match player {
+            Player::First => (self.first, self.second),
+            Player::Second => (self.second, self.first),
+        };
+        let empty = !(current | opponent);
+
+        let mut legal_moves = 0;
+        for direction in 0..8 {
+            let mut candidates = Board::transfer_board(current, direction) & opponent;
+            for _ in 0..5 {
+                candidates |= Board::transfer_board(candidates, direction) & opponent;
+            }
+            legal_moves |= Board::transfer_board(candidates, direction) & empty;
+        }
+        legal_moves
+    }
+
+    /// Stable discs of (first, second). See `stability::stable_discs`.
+    pub fn stable_discs(&self) -> (u64, u64) {
+        stability::stable_discs(self)
+    }
+
+    /// Empty regions connected in the 8 directions. See `region::connected_regions`.
+    pub fn empty_regions(&self) -> Vec<region::Region> {
+        region::connected_regions(self)
+    }
+
+    /// Empty squares of each quadrant. See `region::quadrant_regions`.
+    pub fn quadrant_regions(&self) -> Vec<region::Region> {
+        region::quadrant_regions(self)
+    }
+
+    /// Bitboard of the squares adjacent to `bitboard` in any of the 8 directions.
+    pub fn neighbors(bitboard: u64) -> u64 {
+        (0..8).fold(0, |acc, direction| {
+            acc | Board::transfer_board(bitboard, direction)
+        })
+    }
+
     pub fn calculate_pattern_score(pattern_instance_indices: Vec<u64>) -> f32 {
-        // offsets = np.hstack([[0], (3 ** np.array(n_cells_each_pattern[:10])).cumsum()])
-        let offsets: [usize; 11] = [
-            0, 81, 324, 1053, 3240, 9801, 16362, 22923, 29484, 88533, 147582,
-        ];
+        let offsets = pattern_set::PatternSet::standard().offsets();
 
         let mut total_score = 0.0;
         for (i, pattern_instance_index) in pattern_instance_indices.iter().enumerate() {
             let pattern_instance_index: usize = TryFrom::try_from(*pattern_instance_index).unwrap();
-            total_score += PATTERN_INSTANCES[pattern_instance_index + offsets[i % offsets.len()]];
+            total_score +=
+                pattern_instance_weight(pattern_instance_index + offsets[i % offsets.len()]);
         }
         total_score
     }
@@ -329,6 +387,28 @@ impl Board {
         Board { first, second }
     }
 
+    /// Inverse of `create_from_str`: 8 rows of `o` (first), `x` (second) and `-` (empty)
+    pub fn to_diagram(&self) -> String {
+        (0..8)
+            .map(|y| {
+                (0..8)
+                    .map(|x| {
+                        let cell = 1 << (x + 8 * y);
+                        if self.first & cell != 0 {
+                            "o"
+                        } else if self.second & cell != 0 {
+                            "x"
+                        } else {
+                            "-"
+                        }
+                    })
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     pub fn reverse(board: &Board) -> Board {
         Board {
             first: board.second,
@@ -337,6 +417,16 @@ impl Board {
     }
 }
 
+#[cfg(not(feature = "quantized-weights"))]
+fn pattern_instance_weight(i: usize) -> f32 {
+    PATTERN_INSTANCES[i]
+}
+
+#[cfg(feature = "quantized-weights")]
+fn pattern_instance_weight(i: usize) -> f32 {
+    PATTERN_INSTANCES_QUANTIZED[i] as f32 * PATTERN_SCALE
+}
+
 fn generate_mask(i: u64) -> u64 {
     match i {
         1 => 0b_01010101_01010101_01010101_01010101_01010101_01010101_01010101_01010101,
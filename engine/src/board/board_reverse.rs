@@ -113,7 +113,7 @@ pub static REVERSE_LINE_PATTERN_3: [[usize; 3]; 4] =
     [[5, 14, 23], [40, 49, 58], [2, 9, 16], [47, 54, 61]];
 
 pub static REVERSE_LINE_PATTERN_4: [[usize; 4]; 4] = [
-    [4, 14, 22, 31],
+    [4, 13, 22, 31],
     [32, 41, 50, 59],
     [3, 10, 17, 24],
     [39, 46, 53, 60],
@@ -163,3 +163,64 @@ pub static REVERSE_LINE_PATTERN_8: [[usize; 8]; 18] = [
     [0, 9, 18, 27, 36, 45, 54, 63],
     [7, 14, 21, 28, 35, 42, 49, 56],
 ];
+
+#[cfg(test)]
+mod tests {
+    use crate::board::board_reverse::*;
+    use crate::board::Board;
+    use crate::board::Player;
+
+    fn assert_straight_line(pattern: &[usize]) {
+        let step = pattern[1] as isize - pattern[0] as isize;
+        for cells in pattern.windows(2) {
+            assert_eq!(cells[1] as isize - cells[0] as isize, step, "{:?}", pattern);
+            // x changes at most by one, i.e. the line does not wrap around the board
+            assert!((cells[1] % 8).abs_diff(cells[0] % 8) <= 1, "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn line_patterns_are_straight_lines() {
+        REVERSE_LINE_PATTERN_3
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+        REVERSE_LINE_PATTERN_4
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+        REVERSE_LINE_PATTERN_5
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+        REVERSE_LINE_PATTERN_6
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+        REVERSE_LINE_PATTERN_7
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+        REVERSE_LINE_PATTERN_8
+            .iter()
+            .for_each(|p| assert_straight_line(p));
+    }
+
+    #[test]
+    fn legal_moves_on_diagonal_e1_h4() {
+        let board = Board::create_from_str(
+            "
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - x o -
+            - - o o x x x x
+            - - - o o - x -
+            - - x o x x - -
+            - - o - - - - -
+            - - - - - - - -
+            ",
+        );
+        // f2 flips g3 along the diagonal e1-f2-g3-h4
+        let legal_moves = board.get_all_legal_moves(&Player::Second);
+        assert!(legal_moves.contains(&(1 << 13)));
+        assert_eq!(
+            legal_moves.iter().fold(0, |acc, m| acc | m),
+            board.legal_moves(&Player::Second)
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::fmt;
+use std::fs;
+use std::sync::LazyLock;
+
+use super::bitboard::pattern::STANDARD_PATTERNS;
+use super::symmetry;
+use super::Board;
+use super::Player;
+
+static STANDARD_PATTERN_SET: LazyLock<PatternSet> = LazyLock::new(|| {
+    let definitions = STANDARD_PATTERNS
+        .iter()
+        .map(|(name, cells)| PatternDefinition {
+            name: name.to_string(),
+            cells: cells.to_vec(),
+        })
+        .collect();
+    PatternSet::create(definitions, Symmetry::Rotation).unwrap()
+});
+
+/// Symmetries applied to each pattern definition to generate its instances
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    /// Rotations by 0, 90, 180 and 270 degrees
+    Rotation,
+    /// Rotations, followed by the same rotations of the pattern reflected along the diagonal a1-h8
+    RotationAndReflection,
+}
+
+impl Symmetry {
+    pub fn n_transforms(&self) -> usize {
+        match self {
+            Symmetry::Rotation => 4,
+            Symmetry::RotationAndReflection => 8,
+        }
+    }
+
+    /// Rotation in degrees and whether the pattern is reflected, of the `i_transform`-th instance
+    pub fn describe(i_transform: usize) -> (u16, bool) {
+        (90 * (i_transform % 4) as u16, i_transform >= 4)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternDefinition {
+    pub name: String,
+    /// Squares of the pattern (`a1` = 0, `h8` = 63). The first cell is the least significant
+    /// digit of the instance index.
+    pub cells: Vec<usize>,
+}
+
+/// Pattern set of a linear evaluator. The instances, offsets and the number of weights are
+/// derived from the definitions.
+///
+/// The weight of pattern `k` in the state `index` (base 3, 0 = empty, 1 = current player,
+/// 2 = opponent) is at `offsets()[k] + index`, and every symmetric instance of the pattern shares
+/// the same weights. Instances are ordered by transform, then by pattern.
+///
+/// File format:
+///
+/// ```text
+/// symmetry rotation
+/// # name: cells
+/// diag4: d1 c2 b3 a4
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternSet {
+    definitions: Vec<PatternDefinition>,
+    symmetry: Symmetry,
+    instances: Vec<Vec<usize>>,
+    offsets: Vec<usize>,
+    n_weights: usize,
+}
+
+impl PatternSet {
+    pub fn create(
+        definitions: Vec<PatternDefinition>,
+        symmetry: Symmetry,
+    ) -> Result<PatternSet, String> {
+        if definitions.is_empty() {
+            return Err("pattern set is empty".to_string());
+        }
+        for definition in &definitions {
+            let cells = &definition.cells;
+            if cells.is_empty() || cells.len() > 16 {
+                return Err(format!(
+                    "pattern {} should have 1 to 16 cells",
+                    definition.name
+                ));
+            }
+            if let Some(cell) = cells.iter().find(|&&cell| cell >= 64) {
+                return Err(format!("invalid cell {} in {}", cell, definition.name));
+            }
+            if (1..cells.len()).any(|i| cells[..i].contains(&cells[i])) {
+                return Err(format!("duplicated cells in {}", definition.name));
+            }
+        }
+
+        let instances = (0..symmetry.n_transforms())
+            .flat_map(|i_transform| {
+                definitions.iter().map(move |definition| {
+                    definition
+                        .cells
+                        .iter()
+                        .map(|&cell| symmetry::transform_square(i_transform, cell))
+                        .collect()
+                })
+            })
+            .collect();
+
+        let mut offsets = Vec::new();
+        let mut n_weights = 0;
+        for definition in &definitions {
+            offsets.push(n_weights);
+            n_weights += 3usize.pow(definition.cells.len() as u32);
+        }
+
+        Ok(PatternSet {
+            definitions,
+            symmetry,
+            instances,
+            offsets,
+            n_weights,
+        })
+    }
+
+    /// The eleven patterns of `bitboard::pattern::STANDARD_PATTERNS`, which `PATTERN_INSTANCES`
+    /// is trained with.
+    pub fn standard() -> &'static PatternSet {
+        &STANDARD_PATTERN_SET
+    }
+
+    pub fn parse(pattern_set_str: &str) -> Result<PatternSet, String> {
+        let mut symmetry = Symmetry::Rotation;
+        let mut definitions = Vec::new();
+        for line in pattern_set_str.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(symmetry_str) = line.strip_prefix("symmetry ") {
+                symmetry = match symmetry_str.trim() {
+                    "rotation" => Symmetry::Rotation,
+                    "rotation+reflection" => Symmetry::RotationAndReflection,
+                    s => return Err(format!("unknown symmetry {:?}", s)),
+                };
+                continue;
+            }
+            let (name, cells_str) = line
+                .split_once(':')
+                .ok_or(format!("expected `name: cells`, got {:?}", line))?;
+            let cells = cells_str
+                .split_whitespace()
+                .map(parse_cell)
+                .collect::<Result<Vec<usize>, String>>()?;
+            definitions.push(PatternDefinition {
+                name: name.trim().to_string(),
+                cells,
+            });
+        }
+        PatternSet::create(definitions, symmetry)
+    }
+
+    pub fn load(path: &str) -> Result<PatternSet, String> {
+        let pattern_set_str =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        PatternSet::parse(&pattern_set_str)
+    }
+
+    pub fn definitions(&self) -> &[PatternDefinition] {
+        &self.definitions
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Cells of every instance, `symmetry().n_transforms() * definitions().len()` in total
+    pub fn instances(&self) -> &[Vec<usize>] {
+        &self.instances
+    }
+
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Length of the weight table
+    pub fn n_weights(&self) -> usize {
+        self.n_weights
+    }
+
+    /// Index of the pattern definition of the `i_instance`-th instance
+    pub fn kind_of(&self, i_instance: usize) -> usize {
+        i_instance % self.definitions.len()
+    }
+
+    /// Base-3 index of each instance, in the same order as `instances()`
+    pub fn instance_indices(&self, board: &Board, player: &Player) -> Vec<u64> {
+        let (current, opponent) = match player {
+            Player::First => (board.first(), board.second()),
+            Player::Second => (board.second(), board.first()),
+        };
+        self.instances
+            .iter()
+            .map(|cells| {
+                cells.iter().rev().fold(0, |index, &cell| {
+                    3 * index + ((current >> cell) & 1) + 2 * ((opponent >> cell) & 1)
+                })
+            })
+            .collect()
+    }
+
+    /// Indices of the weight table used by the evaluation, i.e. the features of a linear model.
+    pub fn feature_indices(&self, board: &Board, player: &Player) -> Vec<usize> {
+        self.instance_indices(board, player)
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| index as usize + self.offsets[self.kind_of(i)])
+            .collect()
+    }
+
+    /// CSV of `pattern_id,name,n_cells,offset,n_transforms`, read by `python/learn.py`
+    pub fn metadata_csv(&self) -> String {
+        let mut lines = vec!["pattern_id,name,n_cells,offset,n_transforms".to_string()];
+        for (i, definition) in self.definitions.iter().enumerate() {
+            lines.push(format!(
+                "{},{},{},{},{}",
+                i,
+                definition.name,
+                definition.cells.len(),
+                self.offsets[i],
+                self.symmetry.n_transforms()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for PatternSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symmetry = match self.symmetry {
+            Symmetry::Rotation => "rotation",
+            Symmetry::RotationAndReflection => "rotation+reflection",
+        };
+        writeln!(f, "symmetry {}", symmetry)?;
+        for definition in &self.definitions {
+            let cells = definition
+                .cells
+                .iter()
+                .map(|&cell| format!("{}{}", (b'a' + (cell % 8) as u8) as char, cell / 8 + 1))
+                .collect::<Vec<String>>()
+                .join(" ");
+            writeln!(f, "{}: {}", definition.name, cells)?;
+        }
+        Ok(())
+    }
+}
+
+/// `a1` -> 0, `h8` -> 63
+fn parse_cell(cell: &str) -> Result<usize, String> {
+    match cell.as_bytes() {
+        [x @ b'a'..=b'h', y @ b'1'..=b'8'] => Ok((x - b'a') as usize + 8 * (y - b'1') as usize),
+        _ => Err(format!("invalid cell {:?}", cell)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::bitboard::pattern;
+    use crate::board::pattern_set::*;
+
+    #[test]
+    fn standard_pattern_set() {
+        let pattern_set = PatternSet::standard();
+        assert_eq!(pattern_set.instances().len(), pattern::N_PATTERNS);
+        assert_eq!(pattern_set.n_weights(), pattern::N_PATTERN_INSTANCES);
+        assert_eq!(
+            pattern_set.offsets(),
+            [0, 81, 324, 1053, 3240, 9801, 16362, 22923, 29484, 88533, 147582]
+        );
+
+        // rotate: +90, diag4
+        assert_eq!(pattern_set.instances()[11], vec![31, 22, 13, 4]);
+        // rotate: +180, edge+2X
+        assert_eq!(
+            pattern_set.instances()[30],
+            vec![63, 62, 61, 60, 59, 58, 57, 56, 54, 49]
+        );
+        // rotate: +270, 2x5-corner
+        assert_eq!(
+            pattern_set.instances()[42],
+            vec![56, 48, 40, 32, 24, 57, 49, 41, 33, 25]
+        );
+        // rotate: +90, 3x3-corner
+        assert_eq!(
+            pattern_set.instances()[21],
+            vec![7, 15, 23, 6, 14, 22, 5, 13, 21]
+        );
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let pattern_set = PatternSet::parse(
+            "
+            # comment
+            symmetry rotation+reflection
+            corner: a1 b1 a2 b2
+            edge: a1 b1 c1 d1 e1 f1 g1 h1 # line comment
+            ",
+        )
+        .unwrap();
+        assert_eq!(pattern_set.symmetry(), Symmetry::RotationAndReflection);
+        assert_eq!(pattern_set.definitions()[0].cells, vec![0, 1, 8, 9]);
+        assert_eq!(pattern_set.offsets(), [0, 81]);
+        assert_eq!(pattern_set.n_weights(), 81 + 6561);
+        assert_eq!(pattern_set.instances().len(), 16);
+        // reflected edge: a1 a2 ... a8
+        assert_eq!(
+            pattern_set.instances()[9],
+            vec![0, 8, 16, 24, 32, 40, 48, 56]
+        );
+
+        assert_eq!(PatternSet::parse(&pattern_set.to_string()), Ok(pattern_set));
+        assert_eq!(
+            PatternSet::parse(&PatternSet::standard().to_string()).as_ref(),
+            Ok(PatternSet::standard())
+        );
+    }
+
+    #[test]
+    fn parse_invalid_pattern_sets() {
+        assert!(PatternSet::parse("").is_err());
+        assert!(PatternSet::parse("corner a1 b1").is_err());
+        assert!(PatternSet::parse("corner: a1 i1").is_err());
+        assert!(PatternSet::parse("corner: a1 a1").is_err());
+        assert!(PatternSet::parse("symmetry mirror\ncorner: a1").is_err());
+    }
+
+    #[test]
+    fn instance_indices() {
+        let board = Board::create_from_str(
+            "
+            o x - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - o x - - -
+            - - - x o - - -
+            - - - - - - - -
+            - - - - - - - -
+            x - - - - - - o
+            ",
+        );
+        let pattern_set = PatternSet::parse("corner: a1 b1").unwrap();
+        assert_eq!(
+            pattern_set.instance_indices(&board, &Player::First),
+            vec![1 + 2 * 3, 0, 1, 2]
+        );
+        assert_eq!(
+            pattern_set.instance_indices(&board, &Player::Second),
+            vec![2 + 3, 0, 2, 1]
+        );
+        assert_eq!(
+            pattern_set.feature_indices(&board, &Player::First),
+            vec![7, 0, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use super::count_bits;
+use super::Board;
+use super::Player;
+
+const QUADRANTS: [u64; 4] = [
+    0x00_00_00_00_0f_0f_0f_0f,
+    0x00_00_00_00_f0_f0_f0_f0,
+    0x0f_0f_0f_0f_00_00_00_00,
+    0xf0_f0_f0_f0_00_00_00_00,
+];
+
+/// Set of empty squares, with the players who have a legal move into it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub squares: u64,
+    pub first_access: bool,
+    pub second_access: bool,
+}
+
+impl Region {
+    /// `legal_moves` are the legal moves of the first and second players, computed once for all
+    /// the regions of a board
+    fn create(squares: u64, legal_moves: (u64, u64)) -> Region {
+        Region {
+            squares,
+            first_access: legal_moves.0 & squares != 0,
+            second_access: legal_moves.1 & squares != 0,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        count_bits(self.squares)
+    }
+
+    /// The player who moves first into an odd region can also play its last square, unless the
+    /// region is interrupted by passes.
+    pub fn is_odd(&self) -> bool {
+        self.size() % 2 == 1
+    }
+
+    pub fn has_access(&self, player: &Player) -> bool {
+        match player {
+            Player::First => self.first_access,
+            Player::Second => self.second_access,
+        }
+    }
+}
+
+fn legal_moves_of_both(board: &Board) -> (u64, u64) {
+    (
+        board.legal_moves(&Player::First),
+        board.legal_moves(&Player::Second),
+    )
+}
+
+/// Regions of empty squares connected in the 8 directions, ordered by their lowest square
+pub fn connected_regions(board: &Board) -> Vec<Region> {
+    let legal_moves = legal_moves_of_both(board);
+    let mut empty = !(board.first() | board.second());
+    let mut regions = Vec::new();
+    while empty != 0 {
+        let mut squares = empty & empty.wrapping_neg();
+        loop {
+            let next_squares = (squares | Board::neighbors(squares)) & empty;
+            if next_squares == squares {
+                break;
+            }
+            squares = next_squares;
+        }
+        regions.push(Region::create(squares, legal_moves));
+        empty &= !squares;
+    }
+    regions
+}
+
+/// Empty squares of each quadrant (a1-d4, e1-h4, a5-d8, e5-h8), omitting full quadrants
+pub fn quadrant_regions(board: &Board) -> Vec<Region> {
+    let legal_moves = legal_moves_of_both(board);
+    let empty = !(board.first() | board.second());
+    QUADRANTS
+        .iter()
+        .filter(|&&quadrant| quadrant & empty != 0)
+        .map(|&quadrant| Region::create(quadrant & empty, legal_moves))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::region::*;
+
+    fn fixture_board() -> Board {
+        // empty regions: {a1}, {g1, h1, h2}, {c6, c7, d7} and {h8}
+        Board::create_from_str(
+            "
+            - o o o o o - -
+            o o o o o o x -
+            o o o o x x x x
+            o o o x o x x x
+            o o x o x x o x
+            o o - o o x x x
+            o o - - o o x x
+            o o o o o o x -
+        ",
+        )
+    }
+
+    #[test]
+    fn connected_regions_of_endgame() {
+        let regions = connected_regions(&fixture_board());
+        assert_eq!(
+            regions.iter().map(|r| r.squares).collect::<Vec<u64>>(),
+            vec![
+                1,
+                (1 << 6) | (1 << 7) | (1 << 15),
+                (1 << 42) | (1 << 50) | (1 << 51),
+                1 << 63
+            ]
+        );
+        assert_eq!(
+            regions.iter().map(|r| r.size()).collect::<Vec<u64>>(),
+            vec![1, 3, 3, 1]
+        );
+        assert!(regions.iter().all(|r| r.is_odd()));
+
+        // only `x` can flip b2 and c3 from a1, and only `o` can flip g8 from h8
+        assert!(!regions[0].has_access(&Player::First));
+        assert!(regions[0].has_access(&Player::Second));
+        assert!(regions[3].has_access(&Player::First));
+        assert!(!regions[3].has_access(&Player::Second));
+    }
+
+    #[test]
+    fn connected_regions_of_initial_position() {
+        let regions = connected_regions(&crate::board::new_board());
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].size(), 60);
+        assert!(regions[0].first_access && regions[0].second_access);
+    }
+
+    #[test]
+    fn quadrant_regions_of_endgame() {
+        let regions = quadrant_regions(&fixture_board());
+        assert_eq!(
+            regions.iter().map(|r| r.size()).collect::<Vec<u64>>(),
+            vec![1, 3, 3, 1]
+        );
+        assert_eq!(quadrant_regions(&crate::board::new_board()).len(), 4);
+    }
+}
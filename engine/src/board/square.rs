@@ -0,0 +1,93 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::board::bitboard;
+use crate::board::convert_indices_to_bitboard;
+
+/// Square of the board, `x + 8 * y` where a1 is 0 and h8 is 63
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn from_index(index: u8) -> Option<Square> {
+        (index < 64).then_some(Square(index))
+    }
+
+    /// Square of a bitboard with exactly one bit set, e.g. a legal move
+    pub fn from_bitboard(position: u64) -> Option<Square> {
+        (position.count_ones() == 1).then_some(Square(position.trailing_zeros() as u8))
+    }
+
+    /// e.g. `f5`, case insensitive
+    pub fn from_coord(coord: &str) -> Result<Square, String> {
+        let chars: Vec<char> = coord.to_ascii_uppercase().chars().collect();
+        if chars.len() != 2 {
+            return Err(format!("invalid square: {}", coord));
+        }
+        let position = convert_indices_to_bitboard(chars[0], chars[1])?;
+        Ok(Square(position.trailing_zeros() as u8))
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn bitboard(self) -> u64 {
+        1 << self.0
+    }
+
+    pub fn x(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn y(self) -> u8 {
+        self.0 / 8
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            bitboard::put_position_to_coord(Some(self.bitboard())).unwrap()
+        )
+    }
+}
+
+/// Serialized as the coordinate, e.g. `"f5"`
+impl Serialize for Square {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::square::*;
+
+    #[test]
+    fn conversions() {
+        let f5 = Square::from_coord("f5").unwrap();
+        assert_eq!(f5, Square::from_coord("F5").unwrap());
+        assert_eq!(f5.index(), 37);
+        assert_eq!((f5.x(), f5.y()), (5, 4));
+        assert_eq!(f5.bitboard(), 1 << 37);
+        assert_eq!(Square::from_bitboard(1 << 37), Some(f5));
+        assert_eq!(Square::from_index(37), Some(f5));
+        assert_eq!(f5.to_string(), "f5");
+        assert_eq!(serde_json::to_string(&f5).unwrap(), "\"f5\"");
+    }
+
+    #[test]
+    fn invalid_squares() {
+        assert_eq!(Square::from_bitboard(0), None);
+        assert_eq!(Square::from_bitboard(0b11), None);
+        assert_eq!(Square::from_index(64), None);
+        assert!(Square::from_coord("i1").is_err());
+        assert!(Square::from_coord("a9").is_err());
+        assert!(Square::from_coord("a10").is_err());
+    }
+}
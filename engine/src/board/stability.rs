@@ -0,0 +1,281 @@
+use std::sync::LazyLock;
+
+use super::Board;
+
+const SIZE_EDGE_STABILITY: usize = 6561; // 3**8
+
+/// Stable cells of each edge configuration, indexed in the same way as `board_reverse`
+/// (cell `i` contributes `3**i * {0: empty, 1: first, 2: second}`).
+pub static EDGE_STABILITY: LazyLock<[u8; SIZE_EDGE_STABILITY]> = LazyLock::new(get_edge_stability);
+
+static EDGES: [[usize; 8]; 4] = [
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [56, 57, 58, 59, 60, 61, 62, 63],
+    [0, 8, 16, 24, 32, 40, 48, 56],
+    [7, 15, 23, 31, 39, 47, 55, 63],
+];
+
+/// Lines of each axis (horizontal, vertical, diagonal, anti-diagonal) as bitboards
+static LINES: LazyLock<[Vec<u64>; 4]> = LazyLock::new(|| {
+    let mut lines: [Vec<u64>; 4] = Default::default();
+    for i in 0..8 {
+        lines[0].push(0xff << (8 * i));
+        lines[1].push(0x01_01_01_01_01_01_01_01 << i);
+    }
+    for k in 0..15 {
+        let mut diagonal = 0;
+        let mut anti_diagonal = 0;
+        for x in 0..8i32 {
+            let y = k - x;
+            if (0..8).contains(&y) {
+                diagonal |= 1 << (8 * y + x);
+                anti_diagonal |= 1 << (8 * y + 7 - x);
+            }
+        }
+        lines[2].push(diagonal);
+        lines[3].push(anti_diagonal);
+    }
+    lines
+});
+
+pub fn get_edge_stability() -> [u8; SIZE_EDGE_STABILITY] {
+    let mut table: [Option<u8>; SIZE_EDGE_STABILITY] = [None; SIZE_EDGE_STABILITY];
+    for index in 0..SIZE_EDGE_STABILITY {
+        let cells = super::parse_reverse_index(index as u64);
+        edge_stability_inner(&cells, &mut table);
+    }
+    table.map(|stable| stable.unwrap())
+}
+
+fn edge_index(cells: &[u8; 8]) -> usize {
+    cells
+        .iter()
+        .rev()
+        .fold(0, |index, &cell| 3 * index + cell as usize)
+}
+
+/// A disc on an edge is stable when it keeps its color whatever discs are placed on the
+/// remaining empty cells of the edge, whether the placement flips along the edge or not.
+fn edge_stability_inner(cells: &[u8; 8], table: &mut [Option<u8>; SIZE_EDGE_STABILITY]) -> u8 {
+    let index = edge_index(cells);
+    if let Some(stable) = table[index] {
+        return stable;
+    }
+
+    let mut stable: u8 = 0;
+    for (i, &cell) in cells.iter().enumerate() {
+        if cell != 0 {
+            stable |= 1 << i;
+        }
+    }
+
+    for i_empty in 0..8 {
+        if cells[i_empty] != 0 {
+            continue;
+        }
+        for color in [1, 2] {
+            let child = place_on_edge(cells, i_empty, color);
+            let child_stable = edge_stability_inner(&child, table);
+            for i in 0..8 {
+                if cells[i] != child[i] || child_stable & (1 << i) == 0 {
+                    stable &= !(1 << i);
+                }
+            }
+        }
+    }
+
+    table[index] = Some(stable);
+    stable
+}
+
+fn place_on_edge(cells: &[u8; 8], put: usize, color: u8) -> [u8; 8] {
+    let mut placed = *cells;
+    placed[put] = color;
+    let opponent = 3 - color;
+
+    for step in [-1i32, 1] {
+        let mut i = put as i32 + step;
+        while (0..8).contains(&i) && cells[i as usize] == opponent {
+            i += step;
+        }
+        if (0..8).contains(&i) && cells[i as usize] == color {
+            let mut j = put as i32 + step;
+            while j != i {
+                placed[j as usize] = color;
+                j += step;
+            }
+        }
+    }
+    placed
+}
+
+/// Stable discs of (first, second), i.e. discs which can never be flipped.
+///
+/// This is a lower bound: discs on the edges are looked up from `EDGE_STABILITY`, and the other
+/// discs are stable when every line through them is full or guarded by a stable disc of the same
+/// color.
+pub fn stable_discs(board: &Board) -> (u64, u64) {
+    let first = board.first();
+    let second = board.second();
+    let empty = !(first | second);
+
+    let mut edge_stable = 0;
+    for edge in EDGES.iter() {
+        let mut cells = [0; 8];
+        for (cell, &square) in cells.iter_mut().zip(edge.iter()) {
+            if first & (1 << square) != 0 {
+                *cell = 1;
+            } else if second & (1 << square) != 0 {
+                *cell = 2;
+            }
+        }
+        let stable = EDGE_STABILITY[edge_index(&cells)];
+        for (i, &square) in edge.iter().enumerate() {
+            if stable & (1 << i) != 0 {
+                edge_stable |= 1 << square;
+            }
+        }
+    }
+
+    let mut full_lines = [0u64; 4];
+    for (full_line, lines) in full_lines.iter_mut().zip(LINES.iter()) {
+        for line in lines {
+            if line & empty == 0 {
+                *full_line |= line;
+            }
+        }
+    }
+
+    (
+        propagate_stability(first, edge_stable & first, &full_lines),
+        propagate_stability(second, edge_stable & second, &full_lines),
+    )
+}
+
+fn propagate_stability(discs: u64, initial_stable: u64, full_lines: &[u64; 4]) -> u64 {
+    // squares without neighbors in the direction, e.g. the right edge for direction 0
+    let walls: [u64; 8] = std::array::from_fn(|direction| {
+        !Board::transfer_board(u64::MAX, (direction as u8 + 4) % 8)
+    });
+    // direction pair of each axis: horizontal, vertical, diagonal, anti-diagonal
+    let axes: [(u8, u8); 4] = [(0, 4), (2, 6), (3, 7), (1, 5)];
+
+    let mut stable = initial_stable | (discs & full_lines.iter().fold(u64::MAX, |acc, l| acc & l));
+    loop {
+        let mut next_stable = discs;
+        for (&(d1, d2), full_line) in axes.iter().zip(full_lines.iter()) {
+            next_stable &= full_line
+                | walls[d1 as usize]
+                | walls[d2 as usize]
+                | Board::transfer_board(stable, d1)
+                | Board::transfer_board(stable, d2);
+        }
+        next_stable |= stable;
+        if next_stable == stable {
+            return stable;
+        }
+        stable = next_stable;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::stability::*;
+    use crate::board::Board;
+
+    #[test]
+    fn place_on_edge() {
+        let cells = [1, 2, 2, 0, 2, 1, 0, 0];
+        assert_eq!(
+            crate::board::stability::place_on_edge(&cells, 3, 1),
+            [1, 1, 1, 1, 1, 1, 0, 0]
+        );
+        assert_eq!(
+            crate::board::stability::place_on_edge(&cells, 3, 2),
+            [1, 2, 2, 2, 2, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn no_stable_discs_at_initial_position() {
+        let board = crate::board::new_board();
+        assert_eq!(stable_discs(&board), (0, 0));
+    }
+
+    #[test]
+    fn full_board_is_stable() {
+        let board = Board::create_from_str(
+            "
+            o o o o o o o o
+            o x x o o o o o
+            o x o x o x o o
+            o o o x x x x x
+            x x x x x x x x
+            x x o o x x x x
+            x x x x o x x x
+            x x x x x x x x
+        ",
+        );
+        assert_eq!(stable_discs(&board), (board.first(), board.second()));
+    }
+
+    #[test]
+    fn edge_stable_discs() {
+        // c1 can be flipped by `o` at d1, and g8 by `o` at h8
+        let board = Board::create_from_str(
+            "
+            o o x - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            x o o o o o x -
+        ",
+        );
+        let (first, second) = stable_discs(&board);
+        assert_eq!(first, 0b_00000011 | (0b_00111110 << 56));
+        assert_eq!(second, 1 << 56);
+    }
+
+    #[test]
+    fn stability_propagates_from_corner() {
+        let board = Board::create_from_str(
+            "
+            o o o - - - - -
+            o o o - - - - -
+            o o o - - - - -
+            - - - x o - - -
+            - - - o x - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        );
+        // c2, b3 and c3 can be flipped along the anti-diagonals, e.g. by `x` at d1 and a4
+        let (first, second) = stable_discs(&board);
+        assert_eq!(first, 0b_00000001_00000011_00000111);
+        assert_eq!(second, 0);
+    }
+
+    #[test]
+    fn full_lines_make_interior_discs_stable() {
+        // every line through d4 is full, while e4 is not guarded along the column e
+        let board = Board::create_from_str(
+            "
+            x - - o - - o -
+            - x - o - o - -
+            - - x o o - - -
+            o o o x o o o o
+            - - o o o - - -
+            - o - o - o - -
+            o - - o - - o -
+            - - - o - - - o
+        ",
+        );
+        let (first, second) = stable_discs(&board);
+        assert_eq!(second & (1 << 27), 1 << 27);
+        assert_eq!(first & (1 << 28), 0);
+    }
+}
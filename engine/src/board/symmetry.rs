@@ -0,0 +1,79 @@
+use super::Board;
+
+/// Number of symmetries of the board: 4 rotations, with or without a reflection
+pub const N_TRANSFORMS: usize = 8;
+
+/// Square (`a1` = 0, `h8` = 63) moved by the `i_transform`-th symmetry: reflection along the
+/// diagonal a1-h8 when `i_transform >= 4`, followed by `i_transform % 4` rotations by 90 degrees.
+pub fn transform_square(i_transform: usize, square: usize) -> usize {
+    let (mut x, mut y) = (square % 8, square / 8);
+    if i_transform >= 4 {
+        (x, y) = (y, x);
+    }
+    for _ in 0..(i_transform % 4) {
+        (x, y) = (7 - y, x);
+    }
+    x + 8 * y
+}
+
+pub fn transform_bitboard(i_transform: usize, bitboard: u64) -> u64 {
+    let mut bits = bitboard;
+    let mut transformed = 0;
+    while bits != 0 {
+        let square = bits.trailing_zeros() as usize;
+        transformed |= 1 << transform_square(i_transform, square);
+        bits &= bits - 1;
+    }
+    transformed
+}
+
+/// The transform which undoes the `i_transform`-th transform
+pub fn inverse(i_transform: usize) -> usize {
+    if i_transform >= 4 {
+        // reflections are involutions
+        i_transform
+    } else {
+        (4 - i_transform) % 4
+    }
+}
+
+pub fn transform_board(i_transform: usize, board: &Board) -> Board {
+    Board::create(
+        transform_bitboard(i_transform, board.first()),
+        transform_bitboard(i_transform, board.second()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::symmetry::*;
+
+    #[test]
+    fn transform_square_corners() {
+        // a1 -> h1 -> h8 -> a8 by rotations
+        assert_eq!(transform_square(1, 0), 7);
+        assert_eq!(transform_square(2, 0), 63);
+        assert_eq!(transform_square(3, 0), 56);
+        // b1 -> a2 by the reflection
+        assert_eq!(transform_square(4, 1), 8);
+    }
+
+    #[test]
+    fn inverse_transforms() {
+        let bitboard = 0x00_00_10_08_3c_00_00_01;
+        for i_transform in 0..N_TRANSFORMS {
+            let transformed = transform_bitboard(i_transform, bitboard);
+            assert_eq!(
+                transform_bitboard(inverse(i_transform), transformed),
+                bitboard
+            );
+        }
+    }
+
+    #[test]
+    fn initial_position_is_symmetric_under_rotation_by_180() {
+        let board = crate::board::new_board();
+        assert_eq!(transform_board(2, &board), board);
+        assert_ne!(transform_board(1, &board), board);
+    }
+}
@@ -0,0 +1,518 @@
+pub mod builder;
+pub mod expansion;
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::board::symmetry;
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::rng::Rng;
+use crate::search_algorithm::ResultSource;
+use crate::search_algorithm::SearchResult;
+use crate::strategy::error::StrategyError;
+use crate::strategy::Strategy;
+
+const MAGIC: &[u8; 4] = b"RVBK";
+const VERSION: u8 = 1;
+/// current (u64) + opponent (u64) + score (f32) + n_games (u32)
+const NODE_SIZE: usize = 24;
+
+/// Position of the book, seen from the player to move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookNode {
+    /// Negamax score (final disc difference) of the player to move
+    pub score: f32,
+    /// Number of games which reached the position
+    pub n_games: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookMove {
+    pub put_position: u64,
+    /// Score of the player who plays the move
+    pub score: f32,
+    pub n_games: u32,
+}
+
+/// Opening book: positions keyed by (discs of the player to move, discs of the opponent),
+/// canonicalized over the 8 symmetries of the board. The moves of a position are the legal moves
+/// leading to positions in the book, so transpositions share their nodes.
+///
+/// File format (little endian): `RVBK`, version (u8), number of nodes (u32), then for each node
+/// current (u64), opponent (u64), score (f32) and n_games (u32), sorted by key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Book {
+    nodes: HashMap<(u64, u64), BookNode>,
+}
+
+impl Book {
+    /// Canonical key of `board` with `player` to move: the smallest of its symmetric images
+    pub fn key(board: &Board, player: &Player) -> (u64, u64) {
+        let (current, opponent) = match player {
+            Player::First => (board.first(), board.second()),
+            Player::Second => (board.second(), board.first()),
+        };
+        (0..symmetry::N_TRANSFORMS)
+            .map(|i_transform| {
+                (
+                    symmetry::transform_bitboard(i_transform, current),
+                    symmetry::transform_bitboard(i_transform, opponent),
+                )
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// The player to move after `player` has played on `board`, which is `player` again when the
+    /// opponent has to pass.
+    pub fn next_player(board: &Board, player: &Player) -> Player {
+        if board.legal_moves(&player.opponent()) == 0 && board.legal_moves(player) != 0 {
+            player.clone()
+        } else {
+            player.opponent()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, board: &Board, player: &Player) -> Option<&BookNode> {
+        self.nodes.get(&Book::key(board, player))
+    }
+
+    pub fn get_mut(&mut self, board: &Board, player: &Player) -> Option<&mut BookNode> {
+        self.nodes.get_mut(&Book::key(board, player))
+    }
+
+    pub fn insert(&mut self, board: &Board, player: &Player, node: BookNode) {
+        self.nodes.insert(Book::key(board, player), node);
+    }
+
+    /// Moves of `player` on `board` leading to positions in the book
+    pub fn book_moves(&self, board: &Board, player: &Player) -> Vec<BookMove> {
+        board
+            .get_all_legal_moves(player)
+            .into_iter()
+            .filter_map(|put_position| {
+                let mut child = board.clone();
+                child.put_and_reverse(player, put_position);
+                let next_player = Book::next_player(&child, player);
+                self.get(&child, &next_player).map(|node| BookMove {
+                    put_position,
+                    score: if next_player == *player {
+                        node.score
+                    } else {
+                        -node.score
+                    },
+                    n_games: node.n_games,
+                })
+            })
+            .collect()
+    }
+
+    /// A book move chosen uniformly among the moves whose score is within `randomness` of the
+    /// best book move, or `None` when the position is out of book.
+    pub fn choose_move(
+        &self,
+        board: &Board,
+        player: &Player,
+        randomness: f32,
+        rng: &mut Rng,
+    ) -> Option<BookMove> {
+        let moves = self.book_moves(board, player);
+        let best_score = moves.iter().map(|m| m.score).reduce(f32::max)?;
+        let candidates: Vec<BookMove> = moves
+            .into_iter()
+            .filter(|m| m.score >= best_score - randomness)
+            .collect();
+        let i = rng.next_usize(candidates.len());
+        candidates.into_iter().nth(i)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut keys: Vec<&(u64, u64)> = self.nodes.keys().collect();
+        keys.sort();
+
+        let mut bytes = Vec::with_capacity(9 + NODE_SIZE * keys.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for key in keys {
+            let node = &self.nodes[key];
+            bytes.extend_from_slice(&key.0.to_le_bytes());
+            bytes.extend_from_slice(&key.1.to_le_bytes());
+            bytes.extend_from_slice(&node.score.to_le_bytes());
+            bytes.extend_from_slice(&node.n_games.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, String> {
+        if bytes.len() < 9 || &bytes[0..4] != MAGIC {
+            return Err("not an opening book".to_string());
+        }
+        if bytes[4] != VERSION {
+            return Err(format!("unsupported book version {}", bytes[4]));
+        }
+        let n_nodes = u32::from_le_bytes(bytes[5..9].try_into().unwrap()) as usize;
+        if bytes.len() != 9 + NODE_SIZE * n_nodes {
+            return Err(format!(
+                "invalid book size: expected {} nodes, actual {} bytes",
+                n_nodes,
+                bytes.len()
+            ));
+        }
+
+        let mut nodes = HashMap::with_capacity(n_nodes);
+        for chunk in bytes[9..].chunks_exact(NODE_SIZE) {
+            let current = u64::from_le_bytes(chunk[0..8].try_into().unwrap());
+            let opponent = u64::from_le_bytes(chunk[8..16].try_into().unwrap());
+            let score = f32::from_le_bytes(chunk[16..20].try_into().unwrap());
+            let n_games = u32::from_le_bytes(chunk[20..24].try_into().unwrap());
+            nodes.insert((current, opponent), BookNode { score, n_games });
+        }
+        Ok(Book { nodes })
+    }
+
+    pub fn load(path: &str) -> Result<Book, String> {
+        let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Book::from_bytes(&bytes)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    /// Replace the score of every node having book moves by the best score of its book moves,
+    /// so that the scores of the leaves are back-propagated to the root by negamax.
+    pub fn propagate_negamax(&mut self) {
+        // children have more discs than their parents
+        let mut keys: Vec<(u64, u64)> = self.nodes.keys().cloned().collect();
+        keys.sort_by_key(|(current, opponent)| {
+            std::cmp::Reverse((current | opponent).count_ones())
+        });
+
+        for (current, opponent) in keys {
+            let board = Board::create(current, opponent);
+            let best_score = self
+                .book_moves(&board, &Player::First)
+                .iter()
+                .map(|m| m.score)
+                .reduce(f32::max);
+            if let Some(best_score) = best_score {
+                self.nodes.get_mut(&(current, opponent)).unwrap().score = best_score;
+            }
+        }
+    }
+}
+
+/// Plays a book move while the position is in the book, otherwise follows the wrapped strategy.
+pub struct BookStrategy<S: Strategy> {
+    book: Book,
+    strategy: S,
+    /// Book moves whose score is within `randomness` discs of the best one are chosen at random
+    randomness: f32,
+    rng: Rng,
+    /// Set when a position of the game is not in the book, so that the book is not looked up
+    /// until the next game
+    out_of_book: bool,
+    /// Best book reply of the opponent to the last book move
+    book_reply: Option<Square>,
+}
+
+impl<S: Strategy> BookStrategy<S> {
+    pub fn create(book: Book, strategy: S, randomness: f32, rng: Rng) -> BookStrategy<S> {
+        BookStrategy {
+            book,
+            strategy,
+            randomness,
+            rng,
+            out_of_book: false,
+            book_reply: None,
+        }
+    }
+}
+
+impl<S: Strategy> Strategy for BookStrategy<S> {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        if !self.out_of_book
+            && let Some(book_move) =
+                self.book
+                    .choose_move(board, player, self.randomness, &mut self.rng)
+        {
+            let mut child = board.clone();
+            child.put_and_reverse(player, book_move.put_position);
+            self.book_reply = self
+                .book
+                .book_moves(&child, &player.opponent())
+                .into_iter()
+                .max_by(|a, b| a.score.total_cmp(&b.score))
+                .and_then(|reply| Square::from_bitboard(reply.put_position));
+            return Ok(SearchResult::create(
+                Square::from_bitboard(book_move.put_position),
+                book_move.score,
+                ResultSource::Book,
+            ));
+        }
+        self.out_of_book = true;
+        self.strategy.get_next_move(board, player, i_step)
+    }
+
+    /// Scores searched by the wrapped strategy, also in the book, which has the scores of only
+    /// some of the moves
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        self.strategy.root_move_scores(board, player, i_step)
+    }
+
+    fn new_game(&mut self) {
+        self.out_of_book = false;
+        self.book_reply = None;
+        self.strategy.new_game();
+    }
+
+    /// Positions in the book are not searched, and the book move is not chosen here so that the
+    /// random numbers are only drawn by `get_next_move`
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        if !self.out_of_book && !self.book.book_moves(board, player).is_empty() {
+            return Ok(true);
+        }
+        self.strategy.ponder(board, player, i_step, max_n_leaves)
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        if self.out_of_book {
+            self.strategy.expected_reply()
+        } else {
+            self.book_reply
+        }
+    }
+
+    fn win_probability_scale(&self) -> f32 {
+        self.strategy.win_probability_scale()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::symmetry;
+    use crate::book::*;
+    use crate::strategy::NumdiskLookaheadStrategy;
+
+    /// Book of the initial position and two of its children (f5 and d3, which are symmetric)
+    fn fixture_book() -> (Book, Board) {
+        let mut book = Book::default();
+        let board = crate::board::new_board();
+        book.insert(
+            &board,
+            &Player::First,
+            BookNode {
+                score: 0.0,
+                n_games: 10,
+            },
+        );
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37); // f5
+        book.insert(
+            &child,
+            &Player::Second,
+            BookNode {
+                score: -2.0,
+                n_games: 6,
+            },
+        );
+        (book, board)
+    }
+
+    #[test]
+    fn key_is_invariant_under_symmetries() {
+        let mut board = crate::board::new_board();
+        board.put_and_reverse(&Player::First, 1 << 37);
+        for i_transform in 0..symmetry::N_TRANSFORMS {
+            let transformed = symmetry::transform_board(i_transform, &board);
+            assert_eq!(
+                Book::key(&transformed, &Player::Second),
+                Book::key(&board, &Player::Second)
+            );
+        }
+        assert_ne!(
+            Book::key(&board, &Player::Second),
+            Book::key(&board, &Player::First)
+        );
+    }
+
+    #[test]
+    fn book_moves_include_symmetric_moves() {
+        let (book, board) = fixture_book();
+        let moves = book.book_moves(&board, &Player::First);
+        // d3, c4, f5 and e6 lead to the same canonical position
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|m| m.score == 2.0 && m.n_games == 6));
+        assert!(moves.iter().any(|m| m.put_position == 1 << 19)); // d3
+
+        let node = book.get(&board, &Player::First).unwrap();
+        assert_eq!(node.n_games, 10);
+        // the initial position with colors swapped is the initial position rotated by 90 degrees
+        assert!(book.get(&board, &Player::Second).is_some());
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37);
+        assert!(book.get(&child, &Player::First).is_none());
+    }
+
+    #[test]
+    fn choose_move_within_randomness() {
+        let (mut book, board) = fixture_book();
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37); // f5
+        for (put_position, score) in [(1 << 45, 1.0), (1 << 43, 3.0)] {
+            // f6 and d6
+            let mut grandchild = child.clone();
+            grandchild.put_and_reverse(&Player::Second, put_position);
+            book.insert(&grandchild, &Player::First, BookNode { score, n_games: 3 });
+        }
+
+        let mut rng = Rng::create(0);
+        for _ in 0..10 {
+            let book_move = book
+                .choose_move(&child, &Player::Second, 0.0, &mut rng)
+                .unwrap();
+            assert_eq!((book_move.put_position, book_move.score), (1 << 45, -1.0));
+        }
+        let n_d6 = (0..100)
+            .filter(|_| {
+                let book_move = book.choose_move(&child, &Player::Second, 2.0, &mut rng);
+                book_move.unwrap().put_position == 1 << 43
+            })
+            .count();
+        assert!(0 < n_d6 && n_d6 < 100);
+        assert!(book
+            .choose_move(&child, &Player::First, 0.0, &mut rng)
+            .is_none());
+    }
+
+    #[test]
+    fn propagate_negamax() {
+        let (mut book, board) = fixture_book();
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37); // f5
+        for (put_position, score) in [(1 << 45, 1.0), (1 << 43, 3.0)] {
+            // f6 and d6
+            let mut grandchild = child.clone();
+            grandchild.put_and_reverse(&Player::Second, put_position);
+            book.insert(&grandchild, &Player::First, BookNode { score, n_games: 3 });
+        }
+
+        book.propagate_negamax();
+        // white plays f6, which is -1 for white, and f5 is +1 for black
+        assert_eq!(book.get(&child, &Player::Second).unwrap().score, -1.0);
+        assert_eq!(book.get(&board, &Player::First).unwrap().score, 1.0);
+        assert_eq!(book.get(&board, &Player::First).unwrap().n_games, 10);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let (book, _) = fixture_book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), 9 + 2 * NODE_SIZE);
+        assert_eq!(Book::from_bytes(&bytes), Ok(book));
+        assert!(Book::from_bytes(b"RVBK").is_err());
+        assert!(Book::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn book_strategy_forwards_scores_and_expects_book_reply() {
+        let (mut book, board) = fixture_book();
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37); // f5
+        for (put_position, score) in [(1 << 45, 1.0), (1 << 43, 3.0)] {
+            // f6 and d6
+            let mut grandchild = child.clone();
+            grandchild.put_and_reverse(&Player::Second, put_position);
+            book.insert(&grandchild, &Player::First, BookNode { score, n_games: 3 });
+        }
+        let mut strategy = BookStrategy::create(
+            book,
+            NumdiskLookaheadStrategy::default(),
+            0.0,
+            Rng::create(0),
+        );
+
+        // the scores of all the legal moves, without leaving the book
+        let scores = strategy
+            .root_move_scores(&board, &Player::First, 0)
+            .unwrap();
+        assert_eq!(scores.len(), 4);
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert_eq!(result.source, ResultSource::Book);
+
+        // f6 for f5, or its symmetric image for the other moves
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, result.put_position());
+        let book_moves = strategy.book.book_moves(&child, &Player::Second);
+        let best_reply = book_moves.iter().find(|m| m.score == -1.0).unwrap();
+        assert_eq!(
+            strategy.expected_reply(),
+            Square::from_bitboard(best_reply.put_position)
+        );
+    }
+
+    #[test]
+    fn book_strategy_falls_back_to_search() {
+        let (book, board) = fixture_book();
+        let mut strategy = BookStrategy::create(
+            book,
+            NumdiskLookaheadStrategy::default(),
+            0.0,
+            Rng::create(0),
+        );
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert_eq!(result.score, 2.0);
+        assert_eq!(result.source, ResultSource::Book);
+        assert!([1 << 19, 1 << 26, 1 << 37, 1 << 44].contains(&result.put_position()));
+
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, result.put_position());
+        let result = strategy.get_next_move(&child, &Player::Second, 1).unwrap();
+        assert!(result.best_move.is_some());
+        assert_eq!(result.source, ResultSource::Search);
+
+        // the book is not looked up after leaving it, until the next game
+        assert_ne!(
+            strategy
+                .get_next_move(&board, &Player::First, 0)
+                .unwrap()
+                .score,
+            2.0
+        );
+        strategy.new_game();
+        assert_eq!(
+            strategy
+                .get_next_move(&board, &Player::First, 0)
+                .unwrap()
+                .score,
+            2.0
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use crate::board;
+use crate::board::Board;
+use crate::board::Player;
+use crate::book::Book;
+use crate::book::BookNode;
+use crate::strategy::Strategy;
+
+/// Score of the positions without book moves, before `Book::propagate_negamax`
+#[derive(Clone, Debug, PartialEq)]
+pub enum LeafScore {
+    /// Average final disc difference of the games
+    AverageResult,
+    /// Average final disc difference weighted by the mean rating of the players of each game
+    RatingWeightedResult,
+    /// Score returned by a strategy (see `BookBuilder::build`), e.g. a pattern evaluation search
+    Search,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookBuildConfig {
+    /// Positions up to `max_ply` moves (not counting passes) from the initial position are added
+    pub max_ply: usize,
+    /// Positions reached by fewer games are dropped
+    pub min_games: u32,
+    pub leaf_score: LeafScore,
+}
+
+impl Default for BookBuildConfig {
+    fn default() -> BookBuildConfig {
+        BookBuildConfig {
+            max_ply: 20,
+            min_games: 2,
+            leaf_score: LeafScore::AverageResult,
+        }
+    }
+}
+
+/// Statistics of a position over the games, from the viewpoint of the player to move
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionStats {
+    pub n_games: u32,
+    result_sum: f64,
+    weighted_result_sum: f64,
+    weight_sum: f64,
+}
+
+impl PositionStats {
+    pub fn average_result(&self) -> f32 {
+        (self.result_sum / self.n_games.max(1) as f64) as f32
+    }
+
+    pub fn rating_weighted_result(&self) -> f32 {
+        if self.weight_sum > 0.0 {
+            (self.weighted_result_sum / self.weight_sum) as f32
+        } else {
+            self.average_result()
+        }
+    }
+}
+
+/// Accumulates the positions of games, then builds a `Book`.
+#[derive(Clone, Debug, Default)]
+pub struct BookBuilder {
+    config: BookBuildConfig,
+    stats: HashMap<(u64, u64), PositionStats>,
+}
+
+impl BookBuilder {
+    pub fn create(config: BookBuildConfig) -> BookBuilder {
+        BookBuilder {
+            config,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// Add a game given as `positions` after each move with the player who played it (see
+    /// `ggf::replay_positions`), the final disc difference from the first player and the ratings
+    /// of the players.
+    pub fn add_game(
+        &mut self,
+        positions: &[(Board, Player)],
+        final_score: f32,
+        ratings: (f64, f64),
+    ) {
+        let weight = (ratings.0 + ratings.1) / 2.0;
+        let initial_position = (board::new_board(), Player::Second);
+        let mut visited = Vec::new();
+
+        for (board, last_player) in std::iter::once(&initial_position)
+            .chain(positions.iter())
+            .take(self.config.max_ply + 1)
+        {
+            let player = Book::next_player(board, last_player);
+            let key = Book::key(board, &player);
+            // transpositions within a game are counted once
+            if visited.contains(&key) {
+                continue;
+            }
+            visited.push(key);
+
+            let result = match player {
+                Player::First => final_score,
+                Player::Second => -final_score,
+            } as f64;
+            let stats = self.stats.entry(key).or_default();
+            stats.n_games += 1;
+            stats.result_sum += result;
+            stats.weighted_result_sum += weight * result;
+            stats.weight_sum += weight;
+        }
+    }
+
+    pub fn n_positions(&self) -> usize {
+        self.stats.len()
+    }
+
+    pub fn stats(&self, board: &Board, player: &Player) -> Option<&PositionStats> {
+        self.stats.get(&Book::key(board, player))
+    }
+
+    /// Build a book of the positions reached by `min_games` games or more. With
+    /// `LeafScore::Search`, the leaves are evaluated by `evaluator`.
+    pub fn build(&self, mut evaluator: Option<&mut dyn Strategy>) -> Result<Book, String> {
+        let mut book = Book::default();
+        for (&(current, opponent), stats) in &self.stats {
+            if stats.n_games < self.config.min_games {
+                continue;
+            }
+            let board = Board::create(current, opponent);
+            book.insert(
+                &board,
+                &Player::First,
+                BookNode {
+                    score: stats.average_result(),
+                    n_games: stats.n_games,
+                },
+            );
+        }
+
+        let leaves: Vec<(u64, u64)> = self
+            .stats
+            .keys()
+            .filter(|&&(current, opponent)| {
+                let board = Board::create(current, opponent);
+                book.get(&board, &Player::First).is_some()
+                    && book.book_moves(&board, &Player::First).is_empty()
+            })
+            .cloned()
+            .collect();
+        for (current, opponent) in leaves {
+            let board = Board::create(current, opponent);
+            let score = match (&self.config.leaf_score, evaluator.as_mut()) {
+                (LeafScore::AverageResult, _) => continue,
+                (LeafScore::RatingWeightedResult, _) => {
+                    self.stats[&(current, opponent)].rating_weighted_result()
+                }
+                (LeafScore::Search, _) if board.legal_moves(&Player::First) == 0 => {
+                    board.score_numdisk(&Player::First)
+                }
+                (LeafScore::Search, Some(evaluator)) => {
+                    let i_step = (current | opponent).count_ones() as usize - 4;
+                    evaluator
+                        .get_next_move(&board, &Player::First, i_step)?
+                        .score
+                }
+                (LeafScore::Search, None) => {
+                    return Err("an evaluator is required to search the leaves".to_string());
+                }
+            };
+            book.get_mut(&board, &Player::First).unwrap().score = score;
+        }
+
+        book.propagate_negamax();
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::builder::*;
+    use crate::test_utils::numdisk_strategy;
+
+    fn fixture_game(moves: &[u64]) -> Vec<(Board, Player)> {
+        let mut board = board::new_board();
+        let mut player = Player::First;
+        let mut positions = Vec::new();
+        for &put_position in moves {
+            board.put_and_reverse(&player, put_position);
+            positions.push((board.clone(), player.clone()));
+            player = player.opponent();
+        }
+        positions
+    }
+
+    fn fixture_builder(leaf_score: LeafScore) -> BookBuilder {
+        let mut builder = BookBuilder::create(BookBuildConfig {
+            max_ply: 2,
+            min_games: 1,
+            leaf_score,
+        });
+        let f5 = 1 << 37;
+        let d6 = 1 << 43;
+        let f6 = 1 << 45;
+        let d3 = 1 << 19;
+        let c4 = 1 << 26;
+        builder.add_game(&fixture_game(&[f5, d6, 1 << 18]), 10.0, (2000.0, 2000.0));
+        builder.add_game(&fixture_game(&[f5, f6, 1 << 44]), -4.0, (1000.0, 1000.0));
+        // d3 c5 and c4 e3 are f5 d6 (perpendicular opening) reflected and rotated
+        builder.add_game(&fixture_game(&[d3, 1 << 34]), 2.0, (1000.0, 1000.0));
+        builder.add_game(&fixture_game(&[c4, 1 << 20]), 6.0, (1000.0, 1000.0));
+        builder
+    }
+
+    #[test]
+    fn add_game_accumulates_symmetric_positions() {
+        let builder = fixture_builder(LeafScore::AverageResult);
+        // initial position, f5, f5 d6 (= d3 c5 = c4 e3) and f5 f6
+        assert_eq!(builder.n_positions(), 4);
+
+        let initial = board::new_board();
+        let stats = builder.stats(&initial, &Player::First).unwrap();
+        assert_eq!(stats.n_games, 4);
+        assert_eq!(stats.average_result(), (10.0 - 4.0 + 2.0 + 6.0) / 4.0);
+
+        let perpendicular = &fixture_game(&[1 << 37, 1 << 43])[1].0;
+        let stats = builder.stats(perpendicular, &Player::First).unwrap();
+        assert_eq!(stats.n_games, 3);
+        assert_eq!(stats.average_result(), 6.0);
+        assert_eq!(
+            stats.rating_weighted_result(),
+            (2.0 * 10.0 + 2.0 + 6.0) / 4.0
+        );
+    }
+
+    #[test]
+    fn build_propagates_negamax() {
+        let book = fixture_builder(LeafScore::AverageResult)
+            .build(None)
+            .unwrap();
+        assert_eq!(book.len(), 4);
+        // black chooses f5, then white chooses f6 (-4 for black) rather than d6 (+6 for black)
+        let initial = board::new_board();
+        let node = book.get(&initial, &Player::First).unwrap();
+        assert_eq!(node.score, -4.0);
+        assert_eq!(node.n_games, 4);
+
+        let book = fixture_builder(LeafScore::RatingWeightedResult)
+            .build(None)
+            .unwrap();
+        assert_eq!(book.get(&initial, &Player::First).unwrap().score, -4.0);
+    }
+
+    #[test]
+    fn build_with_search() {
+        let builder = fixture_builder(LeafScore::Search);
+        assert!(builder.build(None).is_err());
+
+        let mut evaluator = numdisk_strategy(1);
+        let book = builder.build(Some(&mut evaluator)).unwrap();
+        // every leaf is evaluated by a search of depth 1 from the viewpoint of black to move
+        let initial = board::new_board();
+        assert!(book.get(&initial, &Player::First).unwrap().score.abs() <= 5.0);
+    }
+
+    #[test]
+    fn min_games() {
+        let mut builder = fixture_builder(LeafScore::AverageResult);
+        builder.config.min_games = 3;
+        let book = builder.build(None).unwrap();
+        // the initial position, f5 and f5 d6
+        assert_eq!(book.len(), 3);
+        assert_eq!(
+            book.get(&board::new_board(), &Player::First).unwrap().score,
+            6.0
+        );
+    }
+}
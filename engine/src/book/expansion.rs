@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use crate::board;
+use crate::board::Board;
+use crate::board::Player;
+use crate::book::Book;
+use crate::book::BookNode;
+use crate::strategy::Strategy;
+
+/// Node added to the book by `BookExpander::expand_once`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpandedNode {
+    /// Canonical board of the parent position, with the first player to move
+    pub parent: Board,
+    pub put_position: u64,
+    /// Searched score of the move, seen from the player who plays it
+    pub score: f32,
+    /// Score drop of the parent from the best line plus the loss of the move
+    pub drop: f32,
+}
+
+/// Drop-out expansion: repeatedly adds the unexplored move which deviates the least from the best
+/// line of the book, then back-propagates the scores by negamax. The state is the book itself, so
+/// an expansion can be resumed from a saved book.
+pub struct BookExpander {
+    book: Book,
+    /// Positions up to `max_ply` moves from the initial position are added
+    max_ply: usize,
+    /// Best unexplored move of each node and its score, `None` when all the moves are in the book
+    alternatives: HashMap<(u64, u64), Option<(u64, f32)>>,
+}
+
+impl BookExpander {
+    pub fn create(book: Book, max_ply: usize) -> BookExpander {
+        BookExpander {
+            book,
+            max_ply,
+            alternatives: HashMap::new(),
+        }
+    }
+
+    pub fn book(&self) -> &Book {
+        &self.book
+    }
+
+    pub fn into_book(self) -> Book {
+        self.book
+    }
+
+    /// Score drop of each node reachable from the initial position by book moves: the sum of the
+    /// differences between the best book move and the played one along the cheapest line.
+    pub fn drops(&self) -> HashMap<(u64, u64), f32> {
+        let mut keys: Vec<(u64, u64)> = self.book.nodes.keys().cloned().collect();
+        keys.sort_by_key(|(current, opponent)| (current | opponent).count_ones());
+
+        let mut drops = HashMap::new();
+        drops.insert(Book::key(&board::new_board(), &Player::First), 0.0);
+        for key in keys {
+            let Some(&drop) = drops.get(&key) else {
+                continue;
+            };
+            let board = Board::create(key.0, key.1);
+            let score = self.book.nodes[&key].score;
+            for book_move in self.book.book_moves(&board, &Player::First) {
+                let child_key = child_key(&board, book_move.put_position);
+                let child_drop = drop + (score - book_move.score).max(0.0);
+                let entry = drops.entry(child_key).or_insert(f32::INFINITY);
+                *entry = entry.min(child_drop);
+            }
+        }
+        drops
+    }
+
+    /// Search the best unexplored alternative of the node with the smallest drop, add it to the
+    /// book and re-minimax. `None` when no node within `max_ply` has unexplored moves.
+    pub fn expand_once(
+        &mut self,
+        evaluator: &mut dyn Strategy,
+    ) -> Result<Option<ExpandedNode>, String> {
+        if self.book.get(&board::new_board(), &Player::First).is_none() {
+            return Err("the initial position is not in the book".to_string());
+        }
+
+        let mut candidates: Vec<((u64, u64), f32)> = self
+            .drops()
+            .into_iter()
+            .filter(|((current, opponent), _)| {
+                ((current | opponent).count_ones() as usize) < 4 + self.max_ply
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        // the drop of a node is a lower bound of the drop of its alternatives
+        let mut best: Option<ExpandedNode> = None;
+        for (key, drop) in candidates {
+            if best.as_ref().is_some_and(|best| best.drop <= drop) {
+                break;
+            }
+            let Some((put_position, score)) = self.alternative(key, evaluator)? else {
+                continue;
+            };
+            let alternative_drop = drop + (self.book.nodes[&key].score - score).max(0.0);
+            if best
+                .as_ref()
+                .is_none_or(|best| alternative_drop < best.drop)
+            {
+                best = Some(ExpandedNode {
+                    parent: Board::create(key.0, key.1),
+                    put_position,
+                    score,
+                    drop: alternative_drop,
+                });
+            }
+        }
+
+        let Some(expanded) = best else {
+            return Ok(None);
+        };
+        let mut child = expanded.parent.clone();
+        child.put_and_reverse(&Player::First, expanded.put_position);
+        let next_player = Book::next_player(&child, &Player::First);
+        let score = match next_player {
+            Player::First => expanded.score,
+            Player::Second => -expanded.score,
+        };
+        self.book
+            .insert(&child, &next_player, BookNode { score, n_games: 0 });
+        // the new node is no longer an alternative of its parent, nor of the transpositions of
+        // the parent which lead to it
+        let expanded_key = Book::key(&child, &next_player);
+        self.alternatives
+            .retain(|&(current, opponent), alternative| {
+                alternative.is_none_or(|(put_position, _)| {
+                    child_key(&Board::create(current, opponent), put_position) != expanded_key
+                })
+            });
+        self.book.propagate_negamax();
+        Ok(Some(expanded))
+    }
+
+    fn alternative(
+        &mut self,
+        key: (u64, u64),
+        evaluator: &mut dyn Strategy,
+    ) -> Result<Option<(u64, f32)>, String> {
+        if let Some(&alternative) = self.alternatives.get(&key) {
+            return Ok(alternative);
+        }
+
+        let board = Board::create(key.0, key.1);
+        let mut alternative: Option<(u64, f32)> = None;
+        for put_position in board.get_all_legal_moves(&Player::First) {
+            let mut child = board.clone();
+            child.put_and_reverse(&Player::First, put_position);
+            let next_player = Book::next_player(&child, &Player::First);
+            if self.book.get(&child, &next_player).is_some() {
+                continue;
+            }
+            let score = if child.legal_moves(&next_player) == 0 {
+                child.score_numdisk(&Player::First)
+            } else {
+                let i_step = (child.first() | child.second()).count_ones() as usize - 4;
+                let score = evaluator.get_next_move(&child, &next_player, i_step)?.score;
+                match next_player {
+                    Player::First => score,
+                    Player::Second => -score,
+                }
+            };
+            if alternative.is_none_or(|(_, best_score)| score > best_score) {
+                alternative = Some((put_position, score));
+            }
+        }
+        self.alternatives.insert(key, alternative);
+        Ok(alternative)
+    }
+}
+
+fn child_key(board: &Board, put_position: u64) -> (u64, u64) {
+    let mut child = board.clone();
+    child.put_and_reverse(&Player::First, put_position);
+    let next_player = Book::next_player(&child, &Player::First);
+    Book::key(&child, &next_player)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Square;
+    use crate::book::expansion::*;
+    use crate::search_algorithm::ResultSource;
+    use crate::search_algorithm::SearchResult;
+    use crate::strategy::error::StrategyError;
+    use crate::test_utils::numdisk_strategy;
+
+    fn fixture_book() -> Book {
+        let mut book = Book::default();
+        let board = board::new_board();
+        book.insert(
+            &board,
+            &Player::First,
+            BookNode {
+                score: 0.0,
+                n_games: 10,
+            },
+        );
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 37); // f5
+        book.insert(
+            &child,
+            &Player::Second,
+            BookNode {
+                score: -2.0,
+                n_games: 10,
+            },
+        );
+        book.propagate_negamax();
+        book
+    }
+
+    #[test]
+    fn drops_of_best_line() {
+        let expander = BookExpander::create(fixture_book(), 2);
+        let drops = expander.drops();
+        assert_eq!(drops.len(), 2);
+        assert!(drops.values().all(|&drop| drop == 0.0));
+    }
+
+    #[test]
+    fn expand_until_max_ply() {
+        let mut expander = BookExpander::create(fixture_book(), 2);
+        let mut evaluator = numdisk_strategy(1);
+
+        let expanded = expander.expand_once(&mut evaluator).unwrap().unwrap();
+        // the initial position has no unexplored moves since d3, c4 and e6 are symmetric to f5
+        let mut f5 = board::new_board();
+        f5.put_and_reverse(&Player::First, 1 << 37);
+        assert_eq!(
+            Book::key(&expanded.parent, &Player::First),
+            Book::key(&f5, &Player::Second)
+        );
+        assert_eq!(expander.book().len(), 3);
+
+        // d6, f4 and f6 are the replies to f5, and positions after 2 moves are not expanded
+        while expander.expand_once(&mut evaluator).unwrap().is_some() {}
+        let book = expander.into_book();
+        assert_eq!(book.len(), 5);
+        let f5_node = book.get(&f5, &Player::Second).unwrap();
+        let best_reply = book
+            .book_moves(&f5, &Player::Second)
+            .iter()
+            .map(|m| m.score)
+            .reduce(f32::max)
+            .unwrap();
+        assert_eq!(f5_node.score, best_reply);
+        assert_eq!(
+            book.get(&board::new_board(), &Player::First).unwrap().score,
+            -best_reply
+        );
+    }
+
+    #[test]
+    fn resume_from_saved_book() {
+        let mut expander = BookExpander::create(fixture_book(), 2);
+        let mut evaluator = numdisk_strategy(1);
+        expander.expand_once(&mut evaluator).unwrap();
+
+        let book = Book::from_bytes(&expander.book().to_bytes()).unwrap();
+        let mut resumed = BookExpander::create(book, 2);
+        assert!(resumed.expand_once(&mut evaluator).unwrap().is_some());
+        assert!(expander.expand_once(&mut evaluator).unwrap().is_some());
+        assert_eq!(resumed.book(), expander.book());
+    }
+
+    /// Scores `target` as lost by the player to move and the other positions as won, so that the
+    /// move into `target` is the best alternative
+    struct TargetEvaluator {
+        target: (u64, u64),
+    }
+
+    impl Strategy for TargetEvaluator {
+        fn get_next_move(
+            &mut self,
+            board: &Board,
+            player: &Player,
+            _i_step: usize,
+        ) -> Result<SearchResult, StrategyError> {
+            let score = if Book::key(board, player) == self.target {
+                -100.0
+            } else {
+                200.0
+            };
+            let best_move = Square::from_bitboard(board.get_all_legal_moves(player)[0]);
+            Ok(SearchResult::create(best_move, score, ResultSource::Search))
+        }
+    }
+
+    #[test]
+    fn transposed_alternatives_are_invalidated() {
+        // f5 f4 d3 d6 e6 and f5 d6 c4 f4 e6 reach the same position (up to symmetry)
+        let lines = [["f5", "f4", "d3", "d6"], ["f5", "d6", "c4", "f4"]];
+        let mut book = Book::default();
+        let node = BookNode {
+            score: 0.0,
+            n_games: 1,
+        };
+        let mut parents = vec![];
+        for line in lines {
+            let mut board = board::new_board();
+            let mut player = Player::First;
+            book.insert(&board, &player, node);
+            for coord in line {
+                let square = Square::from_coord(coord).unwrap();
+                board.put_and_reverse(&player, square.bitboard());
+                player = player.opponent();
+                book.insert(&board, &player, node);
+            }
+            parents.push(Book::key(&board, &player));
+        }
+        let mut child = board::new_board();
+        let mut player = Player::First;
+        for coord in ["f5", "f4", "d3", "d6", "e6"] {
+            child.put_and_reverse(&player, Square::from_coord(coord).unwrap().bitboard());
+            player = player.opponent();
+        }
+        let mut evaluator = TargetEvaluator {
+            target: Book::key(&child, &player),
+        };
+        let mut expander = BookExpander::create(book, 5);
+        // the transposed child is the best alternative of both parents
+        for &parent in &parents {
+            let (put_position, score) = expander
+                .alternative(parent, &mut evaluator)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                child_key(&Board::create(parent.0, parent.1), put_position),
+                evaluator.target
+            );
+            assert_eq!(score, 100.0);
+        }
+
+        let n_nodes = expander.book().len();
+        let expanded = expander.expand_once(&mut evaluator).unwrap().unwrap();
+        assert_eq!(expanded.score, 100.0);
+        // the other parent searches its next alternative instead of adding the same node again
+        let expanded = expander.expand_once(&mut evaluator).unwrap().unwrap();
+        assert_eq!(expanded.score, -200.0);
+        assert_eq!(expander.book().len(), n_nodes + 2);
+    }
+
+    #[test]
+    fn initial_position_is_required() {
+        let mut expander = BookExpander::create(Book::default(), 2);
+        assert!(expander.expand_once(&mut numdisk_strategy(1)).is_err());
+    }
+}
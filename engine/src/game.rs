@@ -3,9 +3,22 @@ use wasm_bindgen::prelude::*;
 use crate::board::bitboard;
 use crate::board::Player;
 use crate::board::*;
+use crate::book::Book;
+use crate::book::BookStrategy;
 use crate::console_log;
+use crate::neural::NeuralNetwork;
+use crate::opening;
+use crate::ponder;
+use crate::ponder::Ponderer;
+use crate::rng::Rng;
+use crate::search_algorithm::SearchResult;
+use crate::strategy::config::ConfiguredStrategy;
+use crate::strategy::config::StrategyConfig;
+use crate::strategy::error::StrategyError;
+use crate::strategy::level;
 use crate::strategy::*;
 use crate::utils;
+use crate::win_probability;
 
 #[wasm_bindgen]
 pub struct Game {
@@ -13,25 +26,61 @@ pub struct Game {
     current_board: Board,
     history: Vec<u64>,
     opponent_strategy: Box<dyn Strategy>,
+    /// Last move of the opponent strategy, whose score is from the viewpoint of the opponent
+    last_result: Option<SearchResult>,
+    /// `loadBook` is ignored when false, e.g. for the weaker levels
+    use_book: bool,
+    /// Searches of the replies to the human's possible moves (see `startPondering`)
+    ponderer: Option<Ponderer>,
 }
 
 impl Game {
-    fn put_and_reverse_opponent_inner(&mut self) -> Result<Option<u64>, String> {
+    fn create_with_strategy(
+        player_human: Player,
+        opponent_strategy: Box<dyn Strategy>,
+        use_book: bool,
+    ) -> Game {
+        Game {
+            player_human,
+            current_board: new_board(),
+            history: vec![],
+            opponent_strategy,
+            last_result: None,
+            use_book,
+            ponderer: None,
+        }
+    }
+
+    fn create_with_strategy_config(
+        player_human: Player,
+        config: StrategyConfig,
+        seed: u64,
+    ) -> Game {
+        let use_book = config.book.is_some();
+        let opponent_strategy = ConfiguredStrategy::create(config, Rng::create(seed));
+        Game::create_with_strategy(player_human, Box::new(opponent_strategy), use_book)
+    }
+
+    fn put_and_reverse_opponent_inner(&mut self) -> Result<SearchResult, StrategyError> {
         let player = self.player_human.opponent();
-        let next_position_result =
-            self.opponent_strategy
-                .get_next_move(&self.current_board, &player, self.history.len());
-
-        match next_position_result {
-            Ok((best_move, _score)) => {
-                let (_player, put_position) = self
-                    .current_board
-                    .put_and_reverse(&player, best_move.unwrap());
-                self.history.push(put_position);
-                Ok(best_move)
-            }
-            Err(msg) => Err(format!("Skipped because: {}", msg)),
+        self.ponderer = None;
+        let result = self.opponent_strategy.get_next_move(
+            &self.current_board,
+            &player,
+            self.history.len(),
+        )?;
+        let Some(best_move) = result.best_move else {
+            return Err(StrategyError::EmptySearchResult);
+        };
+        if self.current_board.legal_moves(&player) & best_move.bitboard() == 0 {
+            return Err(StrategyError::IllegalMove(best_move));
         }
+
+        self.current_board
+            .put_and_reverse(&player, best_move.bitboard());
+        self.history.push(best_move.bitboard());
+        self.last_result = Some(result.clone());
+        Ok(result)
     }
 }
 
@@ -39,17 +88,86 @@ impl Game {
 impl Game {
     pub fn create(player_human: Player, opponent_strategy_type: StrategyType) -> Game {
         utils::set_panic_hook();
-        let current_board = new_board();
-        let opponent_strategy: Box<dyn Strategy> = match opponent_strategy_type {
-            StrategyType::NumdiskLookahead => Box::new(NumdiskLookaheadStrategy {}),
-            StrategyType::PatternLookahead1 => Box::new(PatternLookahead1Strategy {}),
-        };
-        Game {
+        let opponent_strategy = opponent_strategy_type.create_strategy(utils::now_millis() as u64);
+        Game::create_with_strategy(player_human, opponent_strategy, true)
+    }
+
+    /// Play against the engine at `level` from 1 to 10 (see `level::config`). `seed` initializes
+    /// the random deliberate errors and book moves.
+    #[wasm_bindgen(js_name = createWithLevel)]
+    pub fn create_with_level(player_human: Player, level: u8, seed: u64) -> Result<Game, JsValue> {
+        utils::set_panic_hook();
+        let config = level::config(level).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Game::create_with_strategy_config(
             player_human,
-            current_board,
-            history: vec![],
-            opponent_strategy,
+            config,
+            seed,
+        ))
+    }
+
+    /// Play against the engine described by `config_json` (see `StrategyConfig`). The book file
+    /// of the config is not read, but `loadBook` is enabled when the config has a book.
+    #[wasm_bindgen(js_name = createWithConfig)]
+    pub fn create_with_config(
+        player_human: Player,
+        config_json: &str,
+        seed: u64,
+    ) -> Result<Game, JsValue> {
+        utils::set_panic_hook();
+        let config = StrategyConfig::from_json(config_json).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Game::create_with_strategy_config(
+            player_human,
+            config,
+            seed,
+        ))
+    }
+
+    /// Play against a lookahead search with the neural network evaluator in `network_str`
+    /// (see `NeuralNetwork` for the format).
+    #[wasm_bindgen(js_name = createWithNeuralNetwork)]
+    pub fn create_with_neural_network(
+        player_human: Player,
+        network_str: &str,
+        depth: u8,
+    ) -> Result<Game, JsValue> {
+        utils::set_panic_hook();
+        let network = NeuralNetwork::parse(network_str).map_err(|e| JsValue::from_str(&e))?;
+        let opponent_strategy = LookaheadStrategy::create(
+            depth as u64,
+            500000,
+            move |board: &Board, player: &Player| -> f32 { network.evaluate(board, player) },
+        );
+        Ok(Game::create_with_strategy(
+            player_human,
+            Box::new(opponent_strategy),
+            true,
+        ))
+    }
+
+    /// Play moves from the opening book (see `Book` for the format) before searching.
+    /// Book moves within `randomness` discs of the best one are chosen at random.
+    #[wasm_bindgen(js_name = loadBook)]
+    pub fn load_book(
+        &mut self,
+        book_bytes: &[u8],
+        randomness: f32,
+        seed: u64,
+    ) -> Result<(), JsValue> {
+        if !self.use_book {
+            return Ok(());
         }
+        let book = Book::from_bytes(book_bytes).map_err(|e| JsValue::from_str(&e))?;
+        let strategy = std::mem::replace(
+            &mut self.opponent_strategy,
+            Box::new(NumdiskLookaheadStrategy::default()),
+        );
+        self.opponent_strategy = Box::new(BookStrategy::create(
+            book,
+            strategy,
+            randomness,
+            Rng::create(seed),
+        ));
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = currentBoard)]
@@ -64,30 +182,84 @@ impl Game {
             .put_and_reverse(&self.player_human, put_position);
         self.history.push(put_position);
         self.print_move(&self.player_human, put_position);
+        if let Some(ponderer) = self.ponderer.as_mut() {
+            ponderer.stop();
+        }
+    }
+
+    /// Start searching the replies to the human's possible moves while the human is thinking.
+    /// The search is done by `ponderStep`, which JS calls while it is idle.
+    #[wasm_bindgen(js_name = startPondering)]
+    pub fn start_pondering(&mut self) {
+        self.ponderer = Some(Ponderer::create(
+            &self.current_board,
+            &self.player_human.opponent(),
+            self.history.len(),
+            self.opponent_strategy.expected_reply(),
+        ));
+    }
+
+    /// Search the replies by a bounded step (see `ponder::STEP_N_LEAVES`), and return whether
+    /// pondering should continue. The search of `putAndReverseOpponent` then continues from the
+    /// state filled by the steps, e.g. the transposition table.
+    #[wasm_bindgen(js_name = ponderStep)]
+    pub fn ponder_step(&mut self) -> bool {
+        let Some(ponderer) = self.ponderer.as_mut() else {
+            return false;
+        };
+        match ponderer.step(&mut self.opponent_strategy, ponder::STEP_N_LEAVES) {
+            Ok(has_next) => has_next,
+            Err(msg) => {
+                console_log!("pondering stopped (reason: {})", msg);
+                ponderer.stop();
+                false
+            }
+        }
     }
 
     #[wasm_bindgen(js_name = putAndReverseOpponent)]
     pub fn put_and_reverse_opponent(&mut self) -> js_sys::Array {
         let player = self.player_human.opponent();
         match self.put_and_reverse_opponent_inner() {
-            Ok(Some(best_move)) => {
+            Ok(result) => {
+                let best_move = result.put_position();
                 self.print_move(&player, best_move);
                 match bitboard::put_position_to_xy(best_move) {
                     Some((i, j)) => convert_vec_to_jsarray(vec![i, j]),
                     None => convert_vec_to_jsarray(vec![]),
                 }
             }
-            Ok(_) => {
-                console_log!("passed (reason: best_move.put_position = None)");
-                convert_vec_to_jsarray(vec![])
-            }
-            Err(msg) => {
-                console_log!("passed (reason: {})", msg);
+            Err(error) => {
+                console_log!("passed (reason: {})", error);
                 convert_vec_to_jsarray(vec![])
             }
         }
     }
 
+    /// Last move of the opponent as JSON (see `SearchResult`), e.g. to show its depth and source
+    #[wasm_bindgen(js_name = lastSearchResult)]
+    pub fn last_search_result(&self) -> Option<String> {
+        self.last_result
+            .as_ref()
+            .map(|result| serde_json::to_string(result).unwrap())
+    }
+
+    /// Win probability of the human player estimated by the last search of the opponent, in the
+    /// units of its evaluator (see `Strategy::win_probability_scale`)
+    #[wasm_bindgen(js_name = humanWinProbability)]
+    pub fn human_win_probability(&self) -> Option<f32> {
+        let scale = self.opponent_strategy.win_probability_scale();
+        self.last_result
+            .as_ref()
+            .map(|result| win_probability::win_probability(-result.score, scale))
+    }
+
+    /// Name of the longest named opening played so far, e.g. `Tiger`
+    #[wasm_bindgen(js_name = openingName)]
+    pub fn opening_name(&self) -> Option<String> {
+        opening::opening_name(&opening::replay_moves(&self.history)).map(|name| name.to_string())
+    }
+
     #[wasm_bindgen(js_name = getCurrentAllLegalPosition)]
     pub fn get_current_all_legal_position(&self, player: Player) -> js_sys::Array {
         self.current_board.get_all_legal_position(player)
@@ -106,10 +278,15 @@ impl Game {
 
 #[cfg(test)]
 mod tests {
+    use crate::board::bitboard;
     use crate::board::Board;
     use crate::board::Player;
     use crate::game::Game;
+    use crate::heuristic;
+    use crate::strategy::config::StrategyConfig;
+    use crate::strategy::error::StrategyError;
     use crate::strategy::StrategyType;
+    use crate::win_probability;
 
     #[test]
     fn put_next_move_numdisk_lookahead_1_initial_move() {
@@ -142,9 +319,112 @@ mod tests {
         ",
         );
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Some(1 << 20));
+        assert_eq!(result.unwrap().put_position(), 1 << 20);
         assert_eq!(game.current_board, expected);
+        let p = game.human_win_probability().unwrap();
+        assert!(0.0 < p && p < 1.0);
+    }
+
+    #[test]
+    fn opening_name_of_history() {
+        let mut game = Game::create(Player::First, StrategyType::NumdiskLookahead);
+        assert_eq!(game.opening_name(), None);
+        for (i, j) in [(5, 4), (3, 5), (2, 2), (3, 2), (2, 3)] {
+            // f5 d6 c3 d3 c4, played by both sides through the human API
+            game.put_and_reverse(i, j);
+            game.player_human = game.player_human.opponent();
+        }
+        assert_eq!(game.opening_name(), Some("Tiger".to_string()));
+    }
+
+    #[test]
+    fn create_with_level() {
+        let mut game = Game::create_with_level(Player::First, 1, 0).unwrap();
+        assert!(!game.use_book);
+        game.put_and_reverse(5, 4); // f5
+        assert!(game
+            .put_and_reverse_opponent_inner()
+            .unwrap()
+            .best_move
+            .is_some());
+        assert!(
+            Game::create_with_level(Player::First, 10, 0)
+                .unwrap()
+                .use_book
+        );
+    }
+
+    #[test]
+    fn baseline_opponents() {
+        for strategy_type in [
+            StrategyType::Random,
+            StrategyType::Greedy,
+            StrategyType::Mobility,
+        ] {
+            let mut game = Game::create(Player::First, strategy_type);
+            game.put_and_reverse(5, 4); // f5
+            assert!(game
+                .put_and_reverse_opponent_inner()
+                .unwrap()
+                .best_move
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn reuse_pondered_reply() {
+        let mut game = Game::create(Player::First, StrategyType::NumdiskLookahead);
+        game.put_and_reverse(5, 4); // f5
+        game.put_and_reverse_opponent_inner().unwrap();
+        game.start_pondering();
+        while game.ponder_step() {}
+        assert_eq!(game.ponderer.as_ref().unwrap().n_pending(), 0);
+
+        let human_move = game.current_board.get_all_legal_moves(&Player::First)[0];
+        let (i, j) = bitboard::put_position_to_xy(human_move).unwrap();
+        game.put_and_reverse(i as u8, j as u8);
+        assert!(game
+            .put_and_reverse_opponent_inner()
+            .unwrap()
+            .best_move
+            .is_some());
+        assert!(game.ponderer.is_none());
+        // no longer pondering
+        assert!(!game.ponder_step());
+    }
+
+    #[test]
+    fn create_with_config() {
+        let mut game = Game::create_with_strategy_config(
+            Player::First,
+            StrategyConfig::from_json(r#"{"depth_schedule": [[0, 1]]}"#).unwrap(),
+            0,
+        );
+        assert!(!game.use_book);
+        assert_eq!(game.last_search_result(), None);
+        game.put_and_reverse(5, 4); // f5
+        assert!(game
+            .put_and_reverse_opponent_inner()
+            .unwrap()
+            .best_move
+            .is_some());
+        let json = game.last_search_result().unwrap();
+        assert!(json.contains("\"depth\":1,") && json.contains("\"source\":\"search\""));
+    }
+
+    #[test]
+    fn win_probability_with_scale_of_evaluator() {
+        let mut game =
+            Game::create_with_strategy_config(Player::First, StrategyConfig::heuristic(), 0);
+        game.put_and_reverse(5, 4); // f5
+        let score = game.put_and_reverse_opponent_inner().unwrap().score;
+        assert_eq!(
+            game.human_win_probability(),
+            Some(win_probability::win_probability(
+                -score,
+                heuristic::WIN_PROBABILITY_SCALE
+            ))
+        );
     }
 
     #[test]
@@ -178,10 +458,6 @@ mod tests {
         );
 
         assert_eq!(game.current_board, expected);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            "Skipped because: Result of alpha-beta pruning search is empty".to_string()
-        );
+        assert_eq!(result.unwrap_err(), StrategyError::EmptySearchResult);
     }
 }
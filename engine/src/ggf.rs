@@ -1,8 +1,10 @@
 use std::fmt;
 
 use crate::board;
-use crate::board::bitboard;
+use crate::board::pattern_set::PatternSet;
+use crate::board::Board;
 use crate::board::Player;
+use crate::opening;
 
 type CharTriple = (char, char, char);
 
@@ -21,6 +23,46 @@ pub struct Game {
     moves: Vec<CharTriple>,
 }
 
+impl Game {
+    /// Ratings of (black, white)
+    pub fn ratings(&self) -> (f64, f64) {
+        (self.first_rating, self.second_rating)
+    }
+
+    /// Name of the longest named opening of the game (see `opening::OPENINGS`)
+    pub fn opening_name(&self) -> Option<&'static str> {
+        opening::opening_name(&replay_positions(self))
+    }
+}
+
+/// Written in the format read by `parse`
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "(;GM[{}]PC[{}]DT[{}]PB[{}]PW[{}]RB[{:.2}]RW[{:.2}]TY[{}]RE[{:+.2}]BO[{}]",
+            self.name,
+            self.place,
+            self.datetime,
+            self.first_name,
+            self.second_name,
+            self.first_rating,
+            self.second_rating,
+            self.game_type,
+            self.result_score,
+            self.board_type,
+        )?;
+        for (turn, x, y) in &self.moves {
+            if *x == '*' || *y == '*' {
+                write!(f, "{}[pass]", turn)?;
+            } else {
+                write!(f, "{}[{}{}]", turn, x, y)?;
+            }
+        }
+        write!(f, ";)")
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn new_game(
     name_str: &str,
@@ -86,12 +128,32 @@ impl fmt::Display for PatternInstanceHistory {
     }
 }
 
-pub fn extract_pattern_instance_histories(game: &Game) -> Vec<PatternInstanceHistory> {
+pub fn extract_pattern_instance_histories(
+    game: &Game,
+    pattern_set: &PatternSet,
+) -> Vec<PatternInstanceHistory> {
+    replay_positions(game)
+        .iter()
+        .enumerate() // passes are not counted as steps
+        .map(|(i_move, (board, player))| PatternInstanceHistory {
+            step: i_move,
+            final_score: match player {
+                Player::First => game.result_score,
+                Player::Second => -game.result_score,
+            },
+            pattern_instance_indices_0: pattern_set.instance_indices(board, player),
+            pattern_instance_indices_1: vec![0],
+            pattern_instance_indices_2: vec![0],
+            pattern_instance_indices_3: vec![0],
+        })
+        .collect()
+}
+
+/// Positions after each move (passes are skipped), with the player who played the move.
+pub fn replay_positions(game: &Game) -> Vec<(Board, Player)> {
     let mut board = board::new_board();
-    let mut histories = Vec::new();
-    let mut i_move = 0;
-    for move_ in &game.moves {
-        let (turn, x, y) = move_;
+    let mut positions = Vec::new();
+    for (turn, x, y) in &game.moves {
         if *x == '*' || *y == '*' {
             continue;
         }
@@ -102,23 +164,9 @@ pub fn extract_pattern_instance_histories(game: &Game) -> Vec<PatternInstanceHis
         };
         let put_position = board::convert_indices_to_bitboard(*x, *y).unwrap();
         board.put_and_reverse(&player, put_position);
-
-        let history = PatternInstanceHistory {
-            step: i_move,
-            final_score: match player {
-                Player::First => game.result_score,
-                Player::Second => -game.result_score,
-            },
-            pattern_instance_indices_0: bitboard::extract_pattern_instance_indices(&board, &player),
-            pattern_instance_indices_1: vec![0],
-            pattern_instance_indices_2: vec![0],
-            pattern_instance_indices_3: vec![0],
-        };
-
-        histories.push(history);
-        i_move += 1; // since we don't want to count pass
+        positions.push((board.clone(), player));
     }
-    histories
+    positions
 }
 
 fn consume_until_close_bracket(chars: &[char], i: usize) -> (usize, String) {
@@ -289,4 +337,19 @@ mod tests {
         );
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn opening_name_of_game() {
+        // d3 c5 is f5 d6 reflected along the diagonal a8-h1
+        let game = parse(String::from("(;GM[Othello]PB[a]PW[b]RB[1500.00]RW[1500.00]RE[+2.00]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]B[D3]W[C5];)"));
+        assert_eq!(game.opening_name(), Some("Perpendicular"));
+    }
+
+    #[test]
+    fn format_and_parse() {
+        let game_string = String::from("(;GM[Othello]PC[GGS/os]DT[2000-4-16 11:13 EST]PB[fangr]PW[patzer]RB[1457.12]RW[1631.74]TY[8]RE[-40.00]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]B[E6]W[H8]B[pass]W[G7];)");
+        let game = parse(game_string.clone());
+        assert_eq!(game.to_string(), game_string);
+        assert_eq!(parse(game.to_string()), game);
+    }
 }
@@ -0,0 +1,247 @@
+use crate::board::count_bits;
+use crate::board::Board;
+use crate::board::Player;
+
+const CORNERS: u64 = 0x81_00_00_00_00_00_00_81;
+
+// (corner, X-square, C-squares)
+const CORNER_NEIGHBORHOODS: [(u64, u64, u64); 4] = [
+    (1 << 0, 1 << 9, (1 << 1) | (1 << 8)),
+    (1 << 7, 1 << 14, (1 << 6) | (1 << 15)),
+    (1 << 56, 1 << 49, (1 << 48) | (1 << 57)),
+    (1 << 63, 1 << 54, (1 << 55) | (1 << 62)),
+];
+
+/// Scale of the disc difference of a finished game, so that a won game is preferred to any
+/// heuristic advantage.
+const GAME_OVER_SCALE: f32 = 100.0;
+
+/// Scale of the logistic function of the win probability in units of the heuristic scores (see
+/// `win_probability::DEFAULT_SCALE`), i.e. about the weight of a corner
+pub const WIN_PROBABILITY_SCALE: f32 = 10.0;
+
+/// Weights of each feature. Each feature is the difference between the current player and the
+/// opponent, e.g. `corner` is multiplied by (#corners of current player - #corners of opponent).
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeuristicWeights {
+    pub disc: f32,
+    pub mobility: f32,
+    pub potential_mobility: f32,
+    pub frontier: f32,
+    pub corner: f32,
+    pub x_square: f32,
+    pub c_square: f32,
+    pub stability: f32,
+    /// Multiplied by (#odd empty regions the current player can move into) - (#odd empty regions
+    /// only the opponent can move into), since the player who enters an odd region first is
+    /// expected to play its last move
+    pub parity: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeuristicEvaluator {
+    /// Used while the number of empty squares is larger than 40
+    pub opening: HeuristicWeights,
+    pub midgame: HeuristicWeights,
+    /// Used when the number of empty squares is 20 or less
+    pub endgame: HeuristicWeights,
+}
+
+impl Default for HeuristicEvaluator {
+    fn default() -> HeuristicEvaluator {
+        HeuristicEvaluator {
+            opening: HeuristicWeights {
+                disc: -0.5,
+                mobility: 2.0,
+                potential_mobility: 1.0,
+                frontier: -1.0,
+                corner: 8.0,
+                x_square: -6.0,
+                c_square: -2.0,
+                stability: 2.0,
+                parity: 0.0,
+            },
+            midgame: HeuristicWeights {
+                disc: 0.0,
+                mobility: 2.0,
+                potential_mobility: 1.0,
+                frontier: -1.0,
+                corner: 10.0,
+                x_square: -5.0,
+                c_square: -2.0,
+                stability: 3.0,
+                parity: 1.0,
+            },
+            endgame: HeuristicWeights {
+                disc: 1.0,
+                mobility: 1.0,
+                potential_mobility: 0.5,
+                frontier: -0.5,
+                corner: 6.0,
+                x_square: -2.0,
+                c_square: -1.0,
+                stability: 4.0,
+                parity: 2.0,
+            },
+        }
+    }
+}
+
+impl HeuristicEvaluator {
+    pub fn weights(&self, n_empties: u64) -> &HeuristicWeights {
+        match n_empties {
+            41.. => &self.opening,
+            21..=40 => &self.midgame,
+            _ => &self.endgame,
+        }
+    }
+
+    pub fn evaluate(&self, board: &Board, player: &Player) -> f32 {
+        let (current, opponent) = match player {
+            Player::First => (board.first(), board.second()),
+            Player::Second => (board.second(), board.first()),
+        };
+        let empty = !(current | opponent);
+        let current_moves = board.legal_moves(player);
+        let opponent_moves = board.legal_moves(&player.opponent());
+
+        if current_moves == 0 && opponent_moves == 0 {
+            return GAME_OVER_SCALE * board.score_numdisk(player);
+        }
+
+        let weights = self.weights(count_bits(empty));
+        let diff = |c: u64, o: u64| count_bits(c) as f32 - count_bits(o) as f32;
+
+        let mut score = 0.0;
+        score += weights.disc * diff(current, opponent);
+        score += weights.mobility * diff(current_moves, opponent_moves);
+        score += weights.potential_mobility
+            * diff(
+                Board::neighbors(opponent) & empty,
+                Board::neighbors(current) & empty,
+            );
+        let frontier = Board::neighbors(empty);
+        score += weights.frontier * diff(current & frontier, opponent & frontier);
+        score += weights.corner * diff(current & CORNERS, opponent & CORNERS);
+
+        for (corner, x_square, c_squares) in CORNER_NEIGHBORHOODS {
+            if empty & corner != 0 {
+                score += weights.x_square * diff(current & x_square, opponent & x_square);
+                score += weights.c_square * diff(current & c_squares, opponent & c_squares);
+            }
+        }
+
+        let (first_stable, second_stable) = board.stable_discs();
+        let (current_stable, opponent_stable) = match player {
+            Player::First => (first_stable, second_stable),
+            Player::Second => (second_stable, first_stable),
+        };
+        score += weights.stability * diff(current_stable, opponent_stable);
+
+        let parity: f32 = board
+            .empty_regions()
+            .iter()
+            .filter(|region| region.is_odd())
+            .map(|region| {
+                if region.has_access(player) {
+                    1.0
+                } else if region.has_access(&player.opponent()) {
+                    -1.0
+                } else {
+                    0.0
+                }
+            })
+            .sum();
+        score += weights.parity * parity;
+
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::Player;
+    use crate::heuristic::*;
+
+    fn evaluator_without_parity() -> HeuristicEvaluator {
+        let mut evaluator = HeuristicEvaluator::default();
+        evaluator.opening.parity = 0.0;
+        evaluator.midgame.parity = 0.0;
+        evaluator.endgame.parity = 0.0;
+        evaluator
+    }
+
+    #[test]
+    fn evaluate_is_antisymmetric() {
+        let board = Board::create_from_str(
+            "
+            - - - - - - - -
+            - - - - - - - -
+            - - o - - - - -
+            - - o o o - - -
+            - - - o x - - -
+            - - - - x o - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        );
+        let evaluator = evaluator_without_parity();
+        assert_eq!(
+            evaluator.evaluate(&board, &Player::First),
+            -evaluator.evaluate(&board, &Player::Second)
+        );
+    }
+
+    #[test]
+    fn evaluate_prefers_corner_to_x_square() {
+        let corner = Board::create_from_str(
+            "
+            o - - - - - - -
+            - x - - - - - -
+            - - x - - - - -
+            - - - x o - - -
+            - - - o x - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        );
+        let x_square = Board::create_from_str(
+            "
+            - - - - - - - -
+            - o - - - - - -
+            - - x - - - - -
+            - - - x o - - -
+            - - - o x - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        );
+        let evaluator = HeuristicEvaluator::default();
+        assert!(
+            evaluator.evaluate(&corner, &Player::First)
+                > evaluator.evaluate(&x_square, &Player::First)
+        );
+    }
+
+    #[test]
+    fn evaluate_game_over() {
+        let board = Board::create_from_str(
+            "
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            x x x x x x x x
+            x x x x x x x x
+            x x x x x x x x
+        ",
+        );
+        let evaluator = HeuristicEvaluator::default();
+        assert_eq!(evaluator.evaluate(&board, &Player::First), 1600.0);
+        assert_eq!(evaluator.evaluate(&board, &Player::Second), -1600.0);
+    }
+}
@@ -1,7 +1,24 @@
+pub mod accuracy;
+pub mod arena;
 pub mod board;
+pub mod book;
 mod game;
 pub mod ggf;
+pub mod heuristic;
+pub mod neural;
+pub mod opening;
+#[cfg(not(feature = "quantized-weights"))]
 pub mod parameters;
+#[cfg(feature = "quantized-weights")]
+pub mod parameters_quantized;
+pub mod pattern_weights;
+pub mod ponder;
+pub mod rng;
 pub mod search_algorithm;
 pub mod strategy;
+#[cfg(test)]
+mod test_utils;
+pub mod tournament;
+pub mod training;
 mod utils;
+pub mod win_probability;
@@ -1,5 +1,29 @@
+use reversi::accuracy;
+use reversi::arena;
+use reversi::arena::sprt::{Sprt, SprtConfig, SprtDecision};
+use reversi::board;
+use reversi::board::pattern_set::PatternSet;
+use reversi::board::{Board, Player, Square};
+use reversi::book::builder::{BookBuildConfig, BookBuilder, LeafScore};
+use reversi::book::expansion::BookExpander;
+use reversi::book::Book;
 use reversi::ggf;
+use reversi::neural::NeuralNetwork;
+use reversi::opening;
+use reversi::opening::explorer::Explorer;
+use reversi::pattern_weights;
+use reversi::pattern_weights::{PatternWeights, QuantizedPatternWeights};
+use reversi::ponder::{PonderThread, Ponderer};
+use reversi::rng::Rng;
+use reversi::strategy;
+use reversi::strategy::config;
+use reversi::strategy::config::{ConfiguredStrategy, StrategyConfig};
+use reversi::strategy::LookaheadStrategy;
+use reversi::strategy::Strategy;
+use reversi::tournament::{Tournament, TournamentConfig};
+use reversi::training;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
@@ -8,11 +32,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some("parse-ggf") => {
             // ex. cargo run parse-ggf ~/Downloads/Othello.02e4.ggf > ../python/data/game-histories/02.csv
             let path = std::env::args().nth(2).expect("path is required");
+            let pattern_set = load_pattern_set(std::env::args().nth(3))?;
 
             for (i_game, game_str) in BufReader::new(File::open(path)?).lines().enumerate() {
                 let game: ggf::Game = ggf::parse(game_str?);
                 let pattern_instance_histories: Vec<ggf::PatternInstanceHistory> =
-                    ggf::extract_pattern_instance_histories(&game);
+                    ggf::extract_pattern_instance_histories(&game, &pattern_set);
 
                 for history in pattern_instance_histories {
                     println!("{},{}", i_game, history);
@@ -20,11 +45,496 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
             Ok(())
         }
+        Some("explain") => {
+            // ex. cargo run explain "---------------------------ox------xo---------------------------" black ../python/data/parameters/0925.txt
+            let board_str = std::env::args().nth(2).expect("board is required");
+            let player = match std::env::args().nth(3).as_deref() {
+                Some("white") => Player::Second,
+                _ => Player::First,
+            };
+            let weights = load_weights(std::env::args().nth(4), std::env::args().nth(5))?;
+
+            let board = Board::create_from_str(&board_str);
+            let contributions = weights.explain(&board, &player);
+            println!("{}", pattern_weights::render_explanation(&contributions));
+            Ok(())
+        }
+        Some("compare-evaluators") => {
+            // ex. cargo run --release compare-evaluators network.txt ../python/data/parameters/0925.txt 50 2
+            let network_path = std::env::args().nth(2).expect("network is required");
+            let network = NeuralNetwork::load(&network_path)?;
+            // `-` means the built-in weights
+            let weights = match std::env::args().nth(3) {
+                Some(path) if path != "-" => PatternWeights::load(&path)?,
+                _ => PatternWeights::default(),
+            };
+            let n_openings: usize = std::env::args().nth(4).map_or(Ok(50), |n| n.parse())?;
+            let depth: u64 = std::env::args().nth(5).map_or(Ok(2), |n| n.parse())?;
+
+            let mut neural_strategy = LookaheadStrategy::create(
+                depth,
+                usize::MAX,
+                move |board: &Board, player: &Player| -> f32 { network.evaluate(board, player) },
+            );
+            neural_strategy.disable_logging();
+            let mut pattern_strategy = strategy::pattern_lookahead(depth, usize::MAX, weights);
+            pattern_strategy.disable_logging();
+
+            let mut rng = Rng::create(0);
+            let openings: Vec<Vec<u64>> = (0..n_openings)
+                .map(|_| arena::random_opening(&mut rng, 4))
+                .collect();
+            let result = arena::play_match(&mut neural_strategy, &mut pattern_strategy, &openings)?;
+            println!("neural network vs pattern: {}", result);
+            Ok(())
+        }
+        Some("match") => {
+            // ex. cargo run --release match strong.json weak.json 100 8 games.ggf
+            //     cargo run --release match strong.json weak.json 100 8 games.ggf openings.txt
+            let path_a = std::env::args().nth(2).expect("config is required");
+            let path_b = std::env::args().nth(3).expect("config is required");
+            let n_openings: usize = std::env::args().nth(4).map_or(Ok(50), |n| n.parse())?;
+            let n_threads: usize = std::env::args().nth(5).map_or_else(
+                || Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+                |n| n.parse(),
+            )?;
+            let ggf_path = std::env::args().nth(6);
+
+            let openings: Vec<Vec<u64>> = match std::env::args().nth(7) {
+                // one opening per line such as f5d6c3d3
+                Some(path) => BufReader::new(File::open(path)?)
+                    .lines()
+                    .map(|line| Ok(opening::parse_moves(line?.trim())?))
+                    .take(n_openings)
+                    .collect::<Result<_, Box<dyn std::error::Error>>>()?,
+                None => arena::standard_openings(n_openings)?,
+            };
+
+            let config_a = StrategyConfig::load(&path_a)?;
+            let config_b = StrategyConfig::load(&path_b)?;
+            // the random numbers of the games of each opening are seeded by its index
+            let create_a = |i_opening: usize| {
+                config::build_strategy(config_a.clone(), Rng::create(2 * i_opening as u64))
+            };
+            let create_b = |i_opening: usize| {
+                config::build_strategy(config_b.clone(), Rng::create(2 * i_opening as u64 + 1))
+            };
+            let (result, records) = arena::run_match(&create_a, &create_b, &openings, n_threads)?;
+
+            let (elo, margin) = result.elo_difference();
+            println!("{} vs {}: {}", path_a, path_b, result);
+            println!("Elo difference: {:+.1} +/- {:.1}", elo, margin);
+            if let Some(ggf_path) = ggf_path {
+                let mut ggf_str = String::new();
+                for (i, record) in records.iter().enumerate() {
+                    let (first_name, second_name) = if i % 2 == 0 {
+                        (&path_a, &path_b)
+                    } else {
+                        (&path_b, &path_a)
+                    };
+                    ggf_str += &format!("{}\n", record.to_ggf(first_name, second_name));
+                }
+                std::fs::write(ggf_path, ggf_str)?;
+            }
+            Ok(())
+        }
+        Some("sprt") => {
+            // ex. cargo run --release sprt base.json candidate.json 0 5 20000 8
+            let base_path = std::env::args().nth(2).expect("config is required");
+            let candidate_path = std::env::args().nth(3).expect("config is required");
+            let mut sprt_config = SprtConfig::default();
+            if let Some(elo0) = std::env::args().nth(4) {
+                sprt_config.elo0 = elo0.parse()?;
+            }
+            if let Some(elo1) = std::env::args().nth(5) {
+                sprt_config.elo1 = elo1.parse()?;
+            }
+            let max_games: usize = std::env::args().nth(6).map_or(Ok(20000), |n| n.parse())?;
+            let n_threads: usize = std::env::args().nth(7).map_or_else(
+                || Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+                |n| n.parse(),
+            )?;
+
+            let base_config = StrategyConfig::load(&base_path)?;
+            let candidate_config = StrategyConfig::load(&candidate_path)?;
+            // each opening is played twice, and a batch keeps every thread busy. The openings are
+            // generated for each batch, since the test usually stops long before `max_games`.
+            let mut standard_openings = arena::BalancedOpenings::standard()?;
+            let n_openings = max_games / 2;
+            let batch_size = 4 * n_threads;
+            let (lower, upper) = sprt_config.bounds();
+            let mut sprt = Sprt::create(sprt_config);
+            let mut decision = SprtDecision::Continue;
+            for i_first in (0..n_openings).step_by(batch_size) {
+                let batch = standard_openings.next_batch(batch_size.min(n_openings - i_first))?;
+                if batch.is_empty() {
+                    break;
+                }
+                // the random numbers of the games of each opening are seeded by its index
+                let create_candidate = |i_opening: usize| {
+                    let seed = 2 * (i_first + i_opening) as u64;
+                    config::build_strategy(candidate_config.clone(), Rng::create(seed))
+                };
+                let create_base = |i_opening: usize| {
+                    let seed = 2 * (i_first + i_opening) as u64 + 1;
+                    config::build_strategy(base_config.clone(), Rng::create(seed))
+                };
+                let (result, _) =
+                    arena::run_match(&create_candidate, &create_base, &batch, n_threads)?;
+                decision = sprt.update(&result);
+                let (n_games, llr) = *sprt.trajectory().last().unwrap();
+                println!(
+                    "{} games: LLR {:+.3} ({:+.3}, {:+.3}) {}",
+                    n_games,
+                    llr,
+                    lower,
+                    upper,
+                    sprt.result()
+                );
+                if decision != SprtDecision::Continue {
+                    break;
+                }
+            }
+            let (elo, margin) = sprt.result().elo_difference();
+            println!(
+                "{:?}: Elo difference {:+.1} +/- {:.1}",
+                decision, elo, margin
+            );
+            Ok(())
+        }
+        Some("tournament") => {
+            // ex. cargo run --release tournament tournament.json results.jsonl 8
+            let config_path = std::env::args().nth(2).expect("config is required");
+            let results_path = std::env::args().nth(3).expect("results path is required");
+            let n_threads: usize = std::env::args().nth(4).map_or_else(
+                || Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+                |n| n.parse(),
+            )?;
+
+            let config = TournamentConfig::load(&config_path)?;
+            let openings = arena::standard_openings(config.n_openings)?;
+            if openings.len() < config.n_openings {
+                return Err(format!("only {} openings are found", openings.len()).into());
+            }
+            let names: Vec<String> = config.engines.iter().map(|e| e.name.clone()).collect();
+            let mut tournament = Tournament::create(config)?;
+            tournament.load_results(&results_path)?;
+            for (a, b) in tournament.pairings() {
+                if let Some(result) =
+                    tournament.play_pairing(a, b, &openings, n_threads, &results_path)?
+                {
+                    println!("{} vs {}: {}", names[a], names[b], result);
+                }
+            }
+            println!("{}", tournament.crosstable());
+            for (name, elo) in tournament.ratings() {
+                println!("{:10} {:+7.1}", name, elo);
+            }
+            Ok(())
+        }
+        Some("play") => {
+            // ex. cargo run --release play config.json white
+            // The engine ponders on a background thread while the human is thinking. The book of
+            // the config is not used.
+            let config_path = std::env::args().nth(2).expect("config is required");
+            let player_human = match std::env::args().nth(3).as_deref() {
+                Some("white") => Player::Second,
+                _ => Player::First,
+            };
+            let config = StrategyConfig::load(&config_path)?;
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_millis() as u64;
+            let mut strategy = ConfiguredStrategy::create(config.clone(), Rng::create(seed));
+            if let Some(path) = &config.weights {
+                strategy.set_weights(PatternWeights::load(path)?);
+            }
+
+            let mut board = board::new_board();
+            let mut player = Player::First;
+            let mut i_step = 0;
+            let mut lines = std::io::stdin().lines();
+            while board.legal_moves(&Player::First) | board.legal_moves(&Player::Second) != 0 {
+                if board.legal_moves(&player) == 0 {
+                    println!("{:?} passes", player);
+                    player = player.opponent();
+                    continue;
+                }
+                println!("{}\n", board.to_diagram());
+                if player == player_human {
+                    let thread = PonderThread::spawn(
+                        strategy,
+                        Ponderer::create(&board, &player.opponent(), i_step, None),
+                    );
+                    let put_position = loop {
+                        let Some(line) = lines.next() else {
+                            return Ok(());
+                        };
+                        match Square::from_coord(line?.trim()) {
+                            Ok(square) if board.legal_moves(&player) & square.bitboard() != 0 => {
+                                break square.bitboard();
+                            }
+                            _ => println!("illegal move"),
+                        }
+                    };
+                    let (pondering_strategy, _) = thread.stop()?;
+                    strategy = pondering_strategy;
+                    board.put_and_reverse(&player, put_position);
+                } else {
+                    let result = strategy.get_next_move(&board, &player, i_step)?;
+                    let square = result.best_move.ok_or("no move is found")?;
+                    println!("{} ({:+.1})", square, result.score);
+                    board.put_and_reverse(&player, square.bitboard());
+                }
+                i_step += 1;
+                player = player.opponent();
+            }
+            println!(
+                "{}\n{:+}",
+                board.to_diagram(),
+                board.score_numdisk(&player_human)
+            );
+            Ok(())
+        }
+        Some("selfplay") => {
+            // ex. cargo run --release selfplay ./checkpoints 1000 ../python/data/parameters/0925.txt
+            //     cargo run --release selfplay ./checkpoints 1000 - patterns.txt
+            let checkpoint_dir = std::env::args()
+                .nth(2)
+                .expect("checkpoint directory is required");
+            let mut config = training::SelfPlayConfig::default();
+            if let Some(n_games) = std::env::args().nth(3) {
+                config.n_games = n_games.parse()?;
+            }
+            let weights = load_weights(std::env::args().nth(4), std::env::args().nth(5))?;
+
+            std::fs::create_dir_all(&checkpoint_dir)?;
+            training::run_self_play(&config, weights, std::path::Path::new(&checkpoint_dir))?;
+            Ok(())
+        }
+        Some("train-logistic") => {
+            // ex. cargo run --release train-logistic ~/Downloads/Othello.02e4.ggf weights.txt 10 ../python/data/parameters/0925.txt
+            let path = std::env::args().nth(2).expect("path is required");
+            let output_path = std::env::args().nth(3).expect("output path is required");
+            let mut config = training::LogisticConfig::default();
+            if let Some(epochs) = std::env::args().nth(4) {
+                config.epochs = epochs.parse()?;
+            }
+            let mut weights = load_weights(std::env::args().nth(5), std::env::args().nth(6))?;
+
+            let mut samples = Vec::new();
+            for game_str in BufReader::new(File::open(path)?).lines() {
+                let game: ggf::Game = ggf::parse(game_str?);
+                let positions = ggf::replay_positions(&game);
+                samples.extend(training::logistic_samples(
+                    weights.pattern_set(),
+                    &positions,
+                    game.result_score as f32,
+                    &config,
+                ));
+            }
+            println!(
+                "samples = {}, initial loss = {:.4}",
+                samples.len(),
+                training::logistic_loss(&weights, &samples, config.scale)
+            );
+            for (i_epoch, loss) in training::fit_logistic(&mut weights, &samples, &config)
+                .iter()
+                .enumerate()
+            {
+                println!("epoch = {}, loss = {:.4}", i_epoch + 1, loss);
+            }
+            weights.save(&output_path)?;
+            Ok(())
+        }
+        Some("accuracy") => {
+            // ex. cargo run --release accuracy test.ggf ../python/data/parameters/0925.txt new-weights.txt 5
+            let path = std::env::args().nth(2).expect("path is required");
+            let weights = load_weights(std::env::args().nth(3), None)?;
+            let other_weights = match std::env::args().nth(4) {
+                Some(other_path) => Some(load_weights(Some(other_path), None)?),
+                None => None,
+            };
+            let n_largest_errors: usize = std::env::args().nth(5).map_or(Ok(5), |n| n.parse())?;
+
+            let mut positions = Vec::new();
+            for game_str in BufReader::new(File::open(path)?).lines() {
+                let game: ggf::Game = ggf::parse(game_str?);
+                positions.extend(accuracy::test_positions(
+                    &ggf::replay_positions(&game),
+                    game.result_score as f32,
+                ));
+            }
+
+            let report = accuracy::AccuracyReport::create(
+                |board: &Board, player: &Player| weights.score(board, player),
+                &positions,
+                n_largest_errors,
+            );
+            println!("{}", report);
+            if let Some(other_weights) = other_weights {
+                let other_report = accuracy::AccuracyReport::create(
+                    |board: &Board, player: &Player| other_weights.score(board, player),
+                    &positions,
+                    0,
+                );
+                println!("{}", accuracy::render_comparison(&report, &other_report));
+            }
+            Ok(())
+        }
+        Some("openings") => {
+            // ex. cargo run --release openings ~/Downloads/Othello.02e4.ggf
+            let path = std::env::args().nth(2).expect("path is required");
+            // name -> (games, sum of the disc differences, black wins)
+            let mut stats: HashMap<&str, (usize, f64, usize)> = HashMap::new();
+            for game_str in BufReader::new(File::open(path)?).lines() {
+                let game: ggf::Game = ggf::parse(game_str?);
+                let name = game.opening_name().unwrap_or("(unnamed)");
+                let entry = stats.entry(name).or_default();
+                entry.0 += 1;
+                entry.1 += game.result_score;
+                if game.result_score > 0.0 {
+                    entry.2 += 1;
+                }
+            }
+
+            let mut stats: Vec<(&str, (usize, f64, usize))> = stats.into_iter().collect();
+            stats.sort_by_key(|(name, (n_games, _, _))| (std::cmp::Reverse(*n_games), *name));
+            println!("opening,n_games,black_score,black_win_rate");
+            for (name, (n_games, score_sum, n_black_wins)) in stats {
+                println!(
+                    "{},{},{:.2},{:.3}",
+                    name,
+                    n_games,
+                    score_sum / n_games as f64,
+                    n_black_wins as f64 / n_games as f64
+                );
+            }
+            Ok(())
+        }
+        Some("explore") => {
+            // ex. cargo run --release explore ~/Downloads/Othello.02e4.ggf f5d6 json
+            //     `-` means the initial position
+            let path = std::env::args().nth(2).expect("path is required");
+            let moves = match std::env::args().nth(3) {
+                Some(moves_str) if moves_str != "-" => opening::parse_moves(&moves_str)?,
+                _ => vec![],
+            };
+            let mut explorer = Explorer::create(&moves)?;
+            for game_str in BufReader::new(File::open(path)?).lines() {
+                let game: ggf::Game = ggf::parse(game_str?);
+                explorer.add_game(
+                    &ggf::replay_positions(&game),
+                    game.result_score,
+                    game.ratings(),
+                );
+            }
+            match std::env::args().nth(4).as_deref() {
+                Some("json") => println!("{}", explorer.to_json()),
+                _ => print!("{}", explorer),
+            }
+            Ok(())
+        }
+        Some("book") => match std::env::args().nth(2).as_deref() {
+            Some("build") => {
+                // ex. cargo run --release book build ~/Downloads/Othello.02e4.ggf book.bin 20 2 search:4
+                let path = std::env::args().nth(3).expect("path is required");
+                let output_path = std::env::args().nth(4).expect("output path is required");
+                let mut config = BookBuildConfig::default();
+                if let Some(max_ply) = std::env::args().nth(5) {
+                    config.max_ply = max_ply.parse()?;
+                }
+                if let Some(min_games) = std::env::args().nth(6) {
+                    config.min_games = min_games.parse()?;
+                }
+                let mut depth = 0;
+                config.leaf_score = match std::env::args().nth(7).as_deref() {
+                    None | Some("average") => LeafScore::AverageResult,
+                    Some("weighted") => LeafScore::RatingWeightedResult,
+                    Some(leaf) => match leaf.strip_prefix("search:") {
+                        Some(d) => {
+                            depth = d.parse()?;
+                            LeafScore::Search
+                        }
+                        None => return Err(format!("unknown leaf score: {}", leaf).into()),
+                    },
+                };
+
+                let mut builder = BookBuilder::create(config);
+                for game_str in BufReader::new(File::open(path)?).lines() {
+                    let game: ggf::Game = ggf::parse(game_str?);
+                    builder.add_game(
+                        &ggf::replay_positions(&game),
+                        game.result_score as f32,
+                        game.ratings(),
+                    );
+                }
+                println!("positions = {}", builder.n_positions());
+
+                let mut evaluator =
+                    strategy::pattern_lookahead(depth, usize::MAX, PatternWeights::default());
+                evaluator.disable_logging();
+                let book = builder.build(Some(&mut evaluator))?;
+                book.save(&output_path)?;
+                println!("book positions = {}", book.len());
+                Ok(())
+            }
+            Some("expand") => {
+                // ex. cargo run --release book expand book.bin 1000 3600 30 4
+                //     The book is saved every 10 nodes, so that the expansion can be resumed.
+                let book_path = std::env::args().nth(3).expect("book path is required");
+                let max_nodes: usize = std::env::args().nth(4).map_or(Ok(100), |n| n.parse())?;
+                // 0 means no time limit
+                let max_seconds: u64 = std::env::args().nth(5).map_or(Ok(0), |n| n.parse())?;
+                let max_ply: usize = std::env::args().nth(6).map_or(Ok(30), |n| n.parse())?;
+                let depth: u64 = std::env::args().nth(7).map_or(Ok(4), |n| n.parse())?;
+
+                let mut evaluator =
+                    strategy::pattern_lookahead(depth, usize::MAX, PatternWeights::default());
+                evaluator.disable_logging();
+
+                let mut expander = BookExpander::create(Book::load(&book_path)?, max_ply);
+                let started_at = std::time::Instant::now();
+                for i_node in 1..=max_nodes {
+                    if max_seconds > 0 && started_at.elapsed().as_secs() >= max_seconds {
+                        break;
+                    }
+                    let Some(expanded) = expander.expand_once(&mut evaluator)? else {
+                        break;
+                    };
+                    println!(
+                        "node = {}, move = {}, score = {}, drop = {}",
+                        i_node, expanded.put_position, expanded.score, expanded.drop
+                    );
+                    if i_node % 10 == 0 {
+                        expander.book().save(&book_path)?;
+                    }
+                }
+                expander.book().save(&book_path)?;
+                println!("book positions = {}", expander.book().len());
+                Ok(())
+            }
+            subcommand => Err(format!("unknown book subcommand: {:?}", subcommand).into()),
+        },
+        Some("quantize") => {
+            // ex. cargo run quantize ../python/data/parameters/0925.txt src/parameters_quantized.rs
+            let weights_path = std::env::args().nth(2).expect("weights are required");
+            let output_path = std::env::args().nth(3).expect("output path is required");
+            let quantized =
+                QuantizedPatternWeights::quantize(&PatternWeights::load(&weights_path)?);
+            std::fs::write(&output_path, quantized.to_rust_source())?;
+            println!("scale = {}", quantized.scale());
+            Ok(())
+        }
+        Some("pattern-set") => {
+            // ex. cargo run pattern-set patterns.txt > ../python/data/pattern-set.csv
+            let pattern_set = load_pattern_set(std::env::args().nth(2))?;
+            println!("{}", pattern_set.metadata_csv());
+            Ok(())
+        }
         _subcommand => {
             // cargo build --release && cargo profiler callgrind --bin ./target/release/reversi -n 10 bench
             println!("Benchmark");
-            use reversi::board::Board;
-            use reversi::board::Player;
             use reversi::search_algorithm::AlphaBeta;
 
             let current_board = Board::create_from_str(
@@ -50,3 +560,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 }
+
+/// The standard pattern set when `path` is not given
+fn load_pattern_set(path: Option<String>) -> Result<PatternSet, String> {
+    match path {
+        Some(path) => PatternSet::load(&path),
+        None => Ok(PatternSet::standard().clone()),
+    }
+}
+
+/// `-` or no `weights_path` means the built-in weights, or zeros for a custom pattern set
+fn load_weights(
+    weights_path: Option<String>,
+    pattern_set_path: Option<String>,
+) -> Result<PatternWeights, String> {
+    match (weights_path.filter(|path| path != "-"), pattern_set_path) {
+        (Some(path), None) => PatternWeights::load(&path),
+        (None, None) => Ok(PatternWeights::default()),
+        (Some(path), pattern_set_path) => {
+            PatternWeights::load_with_pattern_set(load_pattern_set(pattern_set_path)?, &path)
+        }
+        (None, pattern_set_path) => Ok(PatternWeights::zeros(load_pattern_set(pattern_set_path)?)),
+    }
+}
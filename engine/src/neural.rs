@@ -0,0 +1,273 @@
+use std::fmt;
+use std::fs;
+
+use crate::board::Board;
+use crate::board::Player;
+use crate::rng::Rng;
+
+/// Number of inputs: one plane for the discs of the current player and one for the opponent
+pub const N_INPUTS: usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+struct DenseLayer {
+    n_inputs: usize,
+    n_outputs: usize,
+    /// row-major (n_outputs x n_inputs)
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl DenseLayer {
+    fn forward(&self, inputs: &[f32], outputs: &mut Vec<f32>) {
+        outputs.clear();
+        for (row, bias) in self.weights.chunks(self.n_inputs).zip(self.biases.iter()) {
+            let z: f32 = row.iter().zip(inputs.iter()).map(|(w, x)| w * x).sum();
+            outputs.push(z + bias);
+        }
+    }
+}
+
+/// Multi-layer perceptron evaluator: the two bitboards are fed to dense layers with ReLU
+/// activations, and the last layer outputs the score of the current player.
+///
+/// File format (whitespace or comma separated):
+///
+/// ```text
+/// mlp 128 32 16 1
+/// <weights of layer 1 (row-major)> <biases of layer 1>
+/// <weights of layer 2 (row-major)> <biases of layer 2>
+/// ...
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeuralNetwork {
+    layers: Vec<DenseLayer>,
+}
+
+impl NeuralNetwork {
+    /// Create a network with random weights, e.g. `NeuralNetwork::random(&[128, 32, 1], 0)`
+    pub fn random(layer_sizes: &[usize], seed: u64) -> Result<NeuralNetwork, String> {
+        let mut rng = Rng::create(seed);
+        let mut parameters = Vec::new();
+        for sizes in layer_sizes.windows(2) {
+            let scale = (2.0 / sizes[0] as f32).sqrt();
+            for _ in 0..(sizes[0] * sizes[1]) {
+                parameters.push(scale * (2.0 * rng.next_f32() - 1.0));
+            }
+            parameters.extend(vec![0.0; sizes[1]]);
+        }
+        NeuralNetwork::create(layer_sizes, &parameters)
+    }
+
+    pub fn create(layer_sizes: &[usize], parameters: &[f32]) -> Result<NeuralNetwork, String> {
+        if layer_sizes.len() < 2 || layer_sizes[0] != N_INPUTS || layer_sizes.last() != Some(&1) {
+            return Err(format!(
+                "invalid layer sizes {:?}: the first layer must be {} and the last layer must be 1",
+                layer_sizes, N_INPUTS
+            ));
+        }
+        let n_parameters: usize = layer_sizes.windows(2).map(|s| (s[0] + 1) * s[1]).sum();
+        if parameters.len() != n_parameters {
+            return Err(format!(
+                "invalid number of parameters: expected {}, actual {}",
+                n_parameters,
+                parameters.len()
+            ));
+        }
+
+        let mut layers = Vec::new();
+        let mut offset = 0;
+        for sizes in layer_sizes.windows(2) {
+            let (n_inputs, n_outputs) = (sizes[0], sizes[1]);
+            let weights = parameters[offset..(offset + n_inputs * n_outputs)].to_vec();
+            offset += n_inputs * n_outputs;
+            let biases = parameters[offset..(offset + n_outputs)].to_vec();
+            offset += n_outputs;
+            layers.push(DenseLayer {
+                n_inputs,
+                n_outputs,
+                weights,
+                biases,
+            });
+        }
+        Ok(NeuralNetwork { layers })
+    }
+
+    /// `mlp` and the layer sizes on the first non-empty line, followed by the parameters (see
+    /// `create`). Tokens are separated by whitespace or commas.
+    pub fn parse(network_str: &str) -> Result<NeuralNetwork, String> {
+        let split = |line: &str| -> Vec<String> {
+            line.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_string())
+                .collect()
+        };
+        let mut lines = network_str
+            .lines()
+            .skip_while(|line| line.trim().is_empty());
+        let header = split(lines.next().unwrap_or(""));
+        if header.first().map(String::as_str) != Some("mlp") {
+            return Err("network file should start with `mlp`".to_string());
+        }
+
+        let layer_sizes = header[1..]
+            .iter()
+            .map(|t| t.parse::<usize>().map_err(|e| format!("{:?}: {}", t, e)))
+            .collect::<Result<Vec<usize>, String>>()?;
+        let parameters = lines
+            .flat_map(split)
+            .map(|t| t.parse::<f32>().map_err(|e| format!("{:?}: {}", t, e)))
+            .collect::<Result<Vec<f32>, String>>()?;
+
+        NeuralNetwork::create(&layer_sizes, &parameters)
+    }
+
+    pub fn load(path: &str) -> Result<NeuralNetwork, String> {
+        let network_str =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        NeuralNetwork::parse(&network_str)
+    }
+
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![N_INPUTS];
+        sizes.extend(self.layers.iter().map(|l| l.n_outputs));
+        sizes
+    }
+
+    pub fn evaluate(&self, board: &Board, player: &Player) -> f32 {
+        let (current, opponent) = match player {
+            Player::First => (board.first(), board.second()),
+            Player::Second => (board.second(), board.first()),
+        };
+
+        // The inputs are sparse, so the first layer sums up the columns of the occupied squares.
+        let first_layer = &self.layers[0];
+        let mut activations = first_layer.biases.clone();
+        for (i_plane, plane) in [current, opponent].iter().enumerate() {
+            let mut bits = *plane;
+            while bits != 0 {
+                let i_input = 64 * i_plane + bits.trailing_zeros() as usize;
+                for (i_output, activation) in activations.iter_mut().enumerate() {
+                    *activation += first_layer.weights[i_output * first_layer.n_inputs + i_input];
+                }
+                bits &= bits - 1;
+            }
+        }
+
+        let mut outputs = Vec::new();
+        for layer in &self.layers[1..] {
+            activations.iter_mut().for_each(|a| *a = a.max(0.0));
+            layer.forward(&activations, &mut outputs);
+            std::mem::swap(&mut activations, &mut outputs);
+        }
+        activations[0]
+    }
+}
+
+impl fmt::Display for NeuralNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sizes = self
+            .layer_sizes()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(f, "mlp {}", sizes)?;
+        for layer in &self.layers {
+            let parameters = layer
+                .weights
+                .iter()
+                .chain(layer.biases.iter())
+                .map(|p| p.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+            writeln!(f, "{}", parameters)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::Player;
+    use crate::neural::*;
+
+    #[test]
+    fn create_should_validate_sizes() {
+        assert!(NeuralNetwork::create(&[128, 1], &[0.0; 129]).is_ok());
+        assert!(NeuralNetwork::create(&[128, 1], &[0.0; 128]).is_err());
+        assert!(NeuralNetwork::create(&[64, 1], &[0.0; 65]).is_err());
+        assert!(NeuralNetwork::create(&[128, 2], &[0.0; 258]).is_err());
+    }
+
+    #[test]
+    fn evaluate_linear_network() {
+        // +1 for each disc of the current player, -1 for each disc of the opponent
+        let mut parameters = vec![1.0; 64];
+        parameters.extend(vec![-1.0; 64]);
+        parameters.push(0.5);
+        let network = NeuralNetwork::create(&[128, 1], &parameters).unwrap();
+
+        let board = crate::board::new_board();
+        assert_eq!(network.evaluate(&board, &Player::First), 0.5);
+
+        let board = Board::create_from_str(
+            "
+            o o o - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - o x - - -
+            - - - o o - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        );
+        assert_eq!(network.evaluate(&board, &Player::First), 5.5);
+        assert_eq!(network.evaluate(&board, &Player::Second), -4.5);
+    }
+
+    #[test]
+    fn evaluate_hidden_layer_relu() {
+        // hidden = [relu(#current), relu(-#current)], output = hidden[0] + 2 * hidden[1]
+        let mut parameters = vec![1.0; 64];
+        parameters.extend(vec![0.0; 64]);
+        parameters.extend(vec![-1.0; 64]);
+        parameters.extend(vec![0.0; 64]);
+        parameters.extend([0.0, 0.0, 1.0, 2.0, 0.0]);
+        let network = NeuralNetwork::create(&[128, 2, 1], &parameters).unwrap();
+
+        let board = crate::board::new_board();
+        assert_eq!(network.evaluate(&board, &Player::First), 2.0);
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let network = NeuralNetwork::random(&[128, 8, 4, 1], 0).unwrap();
+        let parsed = NeuralNetwork::parse(&network.to_string()).unwrap();
+        assert_eq!(parsed.layer_sizes(), vec![128, 8, 4, 1]);
+
+        let board = crate::board::new_board();
+        assert_eq!(
+            parsed.evaluate(&board, &Player::First),
+            network.evaluate(&board, &Player::First)
+        );
+        assert!(NeuralNetwork::parse("pattern 128 1").is_err());
+    }
+
+    #[test]
+    fn parse_header_after_blank_lines_and_with_commas() {
+        let network = NeuralNetwork::random(&[128, 2, 1], 0).unwrap();
+        let network_str = network.to_string();
+        let parsed = NeuralNetwork::parse(&format!("\n\n{}", network_str)).unwrap();
+        assert_eq!(parsed.layer_sizes(), vec![128, 2, 1]);
+
+        let (header, parameters) = network_str.split_once('\n').unwrap();
+        let comma_separated = format!("{}\n{}", header.replace(' ', ","), parameters);
+        let parsed = NeuralNetwork::parse(&comma_separated).unwrap();
+        assert_eq!(parsed.layer_sizes(), vec![128, 2, 1]);
+
+        assert!(NeuralNetwork::parse("\n\n").is_err());
+        assert!(NeuralNetwork::parse("\nmlp\n").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+pub mod explorer;
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::board;
+use crate::board::Board;
+use crate::board::Player;
+use crate::book::Book;
+
+/// Named openings with their moves from the initial position, starting with f5.
+/// Other first moves (d3, c4 and e6) are recognized by symmetry.
+pub const OPENINGS: [(&str, &str); 13] = [
+    ("Perpendicular", "f5d6"),
+    ("Diagonal", "f5f6"),
+    ("Parallel", "f5f4"),
+    ("Tiger", "f5d6c3d3c4"),
+    ("Aubrey", "f5d6c3d3c4f4f6f3e6e7"),
+    ("Stephenson", "f5d6c3d3c4f4c5b3c2"),
+    ("Cow", "f5d6c5f4e3"),
+    ("Rose", "f5d6c5f4e3f6g5e6e7"),
+    ("Snake", "f5d6c4d3c5"),
+    ("Buffalo", "f5f6e6f4c3"),
+    ("Rabbit", "f5f6e6f4e3"),
+    ("Heath", "f5f6e6f4g5"),
+    ("Mouse", "f5f4e3f6d3"),
+];
+
+/// Canonical key of the position after the moves of each opening, with the player to move
+static OPENING_KEYS: LazyLock<HashMap<(u64, u64), &'static str>> = LazyLock::new(|| {
+    OPENINGS
+        .iter()
+        .map(|(name, moves_str)| {
+            let moves = parse_moves(moves_str).unwrap();
+            let (board, player) = replay_moves(&moves).pop().unwrap();
+            (
+                Book::key(&board, &Book::next_player(&board, &player)),
+                *name,
+            )
+        })
+        .collect()
+});
+
+/// Moves such as `f5d6c3`
+pub fn parse_moves(moves_str: &str) -> Result<Vec<u64>, String> {
+    let chars: Vec<char> = moves_str.to_ascii_uppercase().chars().collect();
+    if !chars.len().is_multiple_of(2) {
+        return Err(format!("invalid moves: {}", moves_str));
+    }
+    chars
+        .chunks(2)
+        .map(|xy| board::convert_indices_to_bitboard(xy[0], xy[1]))
+        .collect()
+}
+
+/// Positions after each move with the player who played it. The player to move is found from the
+/// legal moves, since passes are not recorded in `moves`.
+pub fn replay_moves(moves: &[u64]) -> Vec<(Board, Player)> {
+    let mut board = board::new_board();
+    let mut player = Player::First;
+    let mut positions = Vec::new();
+    for &put_position in moves {
+        board.put_and_reverse(&player, put_position);
+        positions.push((board.clone(), player.clone()));
+        player = Book::next_player(&board, &player);
+    }
+    positions
+}
+
+/// Name of the longest opening reached by `positions` (see `replay_moves`), up to symmetry and
+/// transposition.
+pub fn opening_name(positions: &[(Board, Player)]) -> Option<&'static str> {
+    positions.iter().rev().find_map(|(board, player)| {
+        let key = Book::key(board, &Book::next_player(board, player));
+        OPENING_KEYS.get(&key).copied()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::opening::*;
+
+    #[test]
+    fn openings_are_legal_and_distinct() {
+        for (name, moves_str) in OPENINGS {
+            let moves = parse_moves(moves_str).unwrap();
+            let mut board = board::new_board();
+            let mut player = Player::First;
+            for put_position in moves {
+                assert!(
+                    board.legal_moves(&player) & put_position != 0,
+                    "{} is illegal",
+                    name
+                );
+                board.put_and_reverse(&player, put_position);
+                player = Book::next_player(&board, &player);
+            }
+        }
+        assert_eq!(OPENING_KEYS.len(), OPENINGS.len());
+    }
+
+    #[test]
+    fn longest_opening_up_to_symmetry() {
+        let tiger = parse_moves("f5d6c3d3c4f4").unwrap();
+        assert_eq!(opening_name(&replay_moves(&tiger)), Some("Tiger"));
+        assert_eq!(
+            opening_name(&replay_moves(&tiger[..4])),
+            Some("Perpendicular")
+        );
+        // Tiger rotated by 180 degrees
+        let rotated = parse_moves("c4e3f6e6f5").unwrap();
+        assert_eq!(opening_name(&replay_moves(&rotated)), Some("Tiger"));
+
+        assert_eq!(opening_name(&replay_moves(&tiger[..1])), None);
+        assert_eq!(opening_name(&[]), None);
+        assert!(parse_moves("f5d").is_err());
+        assert!(parse_moves("f5i9").is_err());
+    }
+
+    #[test]
+    fn shorter_openings_are_recognized_in_longer_ones() {
+        let mut n_extensions = 0;
+        for (name, moves_str) in OPENINGS {
+            for (longer_name, longer_moves_str) in OPENINGS {
+                if longer_name != name && longer_moves_str.starts_with(moves_str) {
+                    let moves = parse_moves(longer_moves_str).unwrap();
+                    let prefix = &moves[..moves_str.len() / 2];
+                    assert_eq!(
+                        opening_name(&replay_moves(prefix)),
+                        Some(name),
+                        "{} in {}",
+                        name,
+                        longer_name
+                    );
+                    n_extensions += 1;
+                }
+            }
+        }
+        assert!(n_extensions >= 10);
+
+        let rose = parse_moves("f5d6c5f4e3f6g5e6e7").unwrap();
+        assert_eq!(opening_name(&replay_moves(&rose[..5])), Some("Cow"));
+        assert_eq!(opening_name(&replay_moves(&rose)), Some("Rose"));
+        let stephenson = parse_moves("f5d6c3d3c4f4c5b3c2").unwrap();
+        assert_eq!(opening_name(&replay_moves(&stephenson[..5])), Some("Tiger"));
+        let buffalo = parse_moves("f5f6e6f4c3").unwrap();
+        assert_eq!(opening_name(&replay_moves(&buffalo)), Some("Buffalo"));
+    }
+}
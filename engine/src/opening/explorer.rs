@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::board;
+use crate::board::bitboard;
+use crate::board::Board;
+use crate::board::Player;
+use crate::book::Book;
+use crate::opening;
+
+/// Move played from the explored position, with the statistics of the games which played it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Continuation {
+    /// Move in the orientation of the explored moves, symmetric moves are merged into one
+    pub put_position: u64,
+    pub n_games: usize,
+    /// Average final disc difference, seen from the player who plays the move
+    pub average_result: f64,
+    /// Average rating of both players of the games
+    pub average_rating: f64,
+}
+
+impl Continuation {
+    pub fn coord(&self) -> String {
+        bitboard::put_position_to_coord(Some(self.put_position)).unwrap()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ContinuationSums {
+    n_games: usize,
+    result_sum: f64,
+    rating_sum: f64,
+}
+
+/// Continuations of a move sequence seen in game archives, up to symmetry and transposition.
+pub struct Explorer {
+    moves: Vec<u64>,
+    board: Board,
+    /// Player to move after `moves`
+    player: Player,
+    /// Canonical key of each child position and the legal move leading to it
+    child_moves: HashMap<(u64, u64), u64>,
+    n_games: usize,
+    sums: HashMap<u64, ContinuationSums>,
+}
+
+impl Explorer {
+    /// Explore the position after `moves` (see `opening::parse_moves`), which must be legal
+    pub fn create(moves: &[u64]) -> Result<Explorer, String> {
+        let mut board = board::new_board();
+        let mut player = Player::First;
+        for &put_position in moves {
+            if board.legal_moves(&player) & put_position == 0 {
+                return Err(format!(
+                    "illegal move: {}",
+                    bitboard::put_position_to_coord(Some(put_position))?
+                ));
+            }
+            board.put_and_reverse(&player, put_position);
+            player = Book::next_player(&board, &player);
+        }
+
+        let mut child_moves = HashMap::new();
+        for put_position in board.get_all_legal_moves(&player) {
+            let mut child = board.clone();
+            child.put_and_reverse(&player, put_position);
+            let key = Book::key(&child, &Book::next_player(&child, &player));
+            // keep the first of the symmetric moves
+            child_moves.entry(key).or_insert(put_position);
+        }
+
+        Ok(Explorer {
+            moves: moves.to_vec(),
+            board,
+            player,
+            child_moves,
+            n_games: 0,
+            sums: HashMap::new(),
+        })
+    }
+
+    /// Add a game given as `positions` (see `ggf::replay_positions`) with the final disc
+    /// difference from the first player and the ratings of the players.
+    pub fn add_game(
+        &mut self,
+        positions: &[(Board, Player)],
+        final_score: f64,
+        ratings: (f64, f64),
+    ) {
+        let n_moves = self.moves.len();
+        let reached = match n_moves {
+            0 => true,
+            _ => positions.get(n_moves - 1).is_some_and(|(board, player)| {
+                Book::key(board, &Book::next_player(board, player))
+                    == Book::key(&self.board, &self.player)
+            }),
+        };
+        if !reached {
+            return;
+        }
+        self.n_games += 1;
+
+        let Some((child, player)) = positions.get(n_moves) else {
+            return;
+        };
+        let key = Book::key(child, &Book::next_player(child, player));
+        let Some(&put_position) = self.child_moves.get(&key) else {
+            return;
+        };
+        let sums = self.sums.entry(put_position).or_default();
+        sums.n_games += 1;
+        sums.result_sum += match player {
+            Player::First => final_score,
+            Player::Second => -final_score,
+        };
+        sums.rating_sum += (ratings.0 + ratings.1) / 2.0;
+    }
+
+    /// Number of games which reached the explored position
+    pub fn n_games(&self) -> usize {
+        self.n_games
+    }
+
+    /// Continuations in descending order of frequency
+    pub fn continuations(&self) -> Vec<Continuation> {
+        let mut continuations: Vec<Continuation> = self
+            .sums
+            .iter()
+            .map(|(&put_position, sums)| Continuation {
+                put_position,
+                n_games: sums.n_games,
+                average_result: sums.result_sum / sums.n_games as f64,
+                average_rating: sums.rating_sum / sums.n_games as f64,
+            })
+            .collect();
+        continuations.sort_by_key(|c| (std::cmp::Reverse(c.n_games), c.put_position));
+        continuations
+    }
+
+    fn moves_str(&self) -> String {
+        self.moves
+            .iter()
+            .map(|&m| bitboard::put_position_to_coord(Some(m)).unwrap())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let continuations: Vec<String> = self
+            .continuations()
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"move\":\"{}\",\"n_games\":{},\"frequency\":{},\"average_result\":{},\"average_rating\":{}}}",
+                    c.coord(),
+                    c.n_games,
+                    c.n_games as f64 / self.n_games as f64,
+                    c.average_result,
+                    c.average_rating
+                )
+            })
+            .collect();
+        format!(
+            "{{\"moves\":\"{}\",\"opening\":{},\"player\":\"{:?}\",\"n_games\":{},\"continuations\":[{}]}}",
+            self.moves_str(),
+            opening::opening_name(&opening::replay_moves(&self.moves))
+                .map_or("null".to_string(), |name| format!("\"{}\"", name)),
+            self.player,
+            self.n_games,
+            continuations.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Explorer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "moves = {}, opening = {}, {:?} to move, games = {}",
+            self.moves_str(),
+            opening::opening_name(&opening::replay_moves(&self.moves)).unwrap_or("-"),
+            self.player,
+            self.n_games
+        )?;
+        writeln!(
+            f,
+            "{:<4} {:>8} {:>8} {:>8} {:>8}",
+            "move", "games", "freq.", "result", "rating"
+        )?;
+        for c in self.continuations() {
+            writeln!(
+                f,
+                "{:<4} {:>8} {:>8.3} {:>+8.2} {:>8.1}",
+                c.coord(),
+                c.n_games,
+                c.n_games as f64 / self.n_games as f64,
+                c.average_result,
+                c.average_rating
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::opening::explorer::*;
+    use crate::opening::parse_moves;
+
+    fn fixture_explorer() -> Explorer {
+        let mut explorer = Explorer::create(&parse_moves("f5").unwrap()).unwrap();
+        for (moves_str, final_score, ratings) in [
+            ("f5d6c3", 10.0, (2000.0, 1800.0)),
+            // c4 e3 is f5 d6 rotated by 180 degrees
+            ("c4e3", -2.0, (1600.0, 1600.0)),
+            ("f5f6", 4.0, (1500.0, 1700.0)),
+            ("f5", 0.0, (1500.0, 1500.0)),
+            ("f5f4", 0.0, (1500.0, 1500.0)),
+        ] {
+            let positions = opening::replay_moves(&parse_moves(moves_str).unwrap());
+            explorer.add_game(&positions, final_score, ratings);
+        }
+        explorer
+    }
+
+    #[test]
+    fn continuations_up_to_symmetry() {
+        let explorer = fixture_explorer();
+        assert_eq!(explorer.n_games(), 5);
+        let continuations = explorer.continuations();
+        assert_eq!(continuations.len(), 3);
+        assert_eq!(
+            continuations[0],
+            Continuation {
+                put_position: 1 << 43, // d6
+                n_games: 2,
+                average_result: (-10.0 + 2.0) / 2.0,
+                average_rating: (1900.0 + 1600.0) / 2.0,
+            }
+        );
+        assert_eq!(continuations[1].coord(), "f4");
+        assert_eq!(continuations[2].coord(), "f6");
+        assert_eq!(continuations[2].average_result, -4.0);
+    }
+
+    #[test]
+    fn render_table_and_json() {
+        let explorer = fixture_explorer();
+        let table = explorer.to_string();
+        assert!(table.starts_with("moves = f5, opening = -, Second to move, games = 5"));
+        assert_eq!(table.lines().count(), 2 + 3);
+
+        let json = explorer.to_json();
+        assert!(json.starts_with(
+            "{\"moves\":\"f5\",\"opening\":null,\"player\":\"Second\",\"n_games\":5,\"continuations\":[{\"move\":\"d6\",\"n_games\":2,\"frequency\":0.4,"
+        ));
+    }
+
+    #[test]
+    fn illegal_moves() {
+        assert!(Explorer::create(&parse_moves("f5f5").unwrap()).is_err());
+        let explorer = Explorer::create(&[]).unwrap();
+        assert_eq!(explorer.child_moves.len(), 1);
+    }
+}
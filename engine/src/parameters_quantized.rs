@@ -0,0 +1,8 @@
+#[rustfmt::skip]
+#[allow(clippy::module_inception)]
+pub mod parameters_quantized {
+    #[allow(dead_code)]
+    pub const PATTERN_SCALE: f32 = 1.0;
+    #[allow(dead_code, clippy::large_const_arrays)]
+    pub const PATTERN_INSTANCES_QUANTIZED: [i16; 167_265] = [0; 167_265];
+}
@@ -0,0 +1,509 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+#[cfg(feature = "quantized-weights")]
+use std::sync::LazyLock;
+
+use crate::board::pattern_set::PatternSet;
+use crate::board::pattern_set::Symmetry;
+use crate::board::Board;
+use crate::board::Player;
+#[cfg(not(feature = "quantized-weights"))]
+use crate::parameters::parameters::PATTERN_INSTANCES;
+#[cfg(feature = "quantized-weights")]
+use crate::parameters_quantized::parameters_quantized::{
+    PATTERN_INSTANCES_QUANTIZED, PATTERN_SCALE,
+};
+use crate::win_probability;
+
+/// `PATTERN_INSTANCES_QUANTIZED` multiplied by the scale, built once
+#[cfg(feature = "quantized-weights")]
+static DEQUANTIZED_PATTERN_INSTANCES: LazyLock<Vec<f32>> = LazyLock::new(|| {
+    PATTERN_INSTANCES_QUANTIZED
+        .iter()
+        .map(|&w| w as f32 * PATTERN_SCALE)
+        .collect()
+});
+
+/// Weight table of all pattern instances of a pattern set. For the standard pattern set, it is
+/// laid out in the same order as `PATTERN_INSTANCES` (and the `coef` column written by
+/// `python/learn.py`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternWeights {
+    pattern_set: Cow<'static, PatternSet>,
+    weights: Cow<'static, [f32]>,
+}
+
+impl Default for PatternWeights {
+    #[cfg(not(feature = "quantized-weights"))]
+    fn default() -> PatternWeights {
+        PatternWeights {
+            pattern_set: Cow::Borrowed(PatternSet::standard()),
+            weights: Cow::Borrowed(&PATTERN_INSTANCES),
+        }
+    }
+
+    #[cfg(feature = "quantized-weights")]
+    fn default() -> PatternWeights {
+        PatternWeights {
+            pattern_set: Cow::Borrowed(PatternSet::standard()),
+            weights: Cow::Borrowed(&DEQUANTIZED_PATTERN_INSTANCES),
+        }
+    }
+}
+
+impl PatternWeights {
+    /// Weights of the standard pattern set
+    pub fn create(weights: Vec<f32>) -> Result<PatternWeights, String> {
+        PatternWeights::create_inner(Cow::Borrowed(PatternSet::standard()), weights)
+    }
+
+    pub fn create_with_pattern_set(
+        pattern_set: PatternSet,
+        weights: Vec<f32>,
+    ) -> Result<PatternWeights, String> {
+        PatternWeights::create_inner(Cow::Owned(pattern_set), weights)
+    }
+
+    /// All-zero weights of `pattern_set`, e.g. the initial weights of training
+    pub fn zeros(pattern_set: PatternSet) -> PatternWeights {
+        let weights = vec![0.0; pattern_set.n_weights()];
+        PatternWeights::create_with_pattern_set(pattern_set, weights).unwrap()
+    }
+
+    fn create_inner(
+        pattern_set: Cow<'static, PatternSet>,
+        weights: Vec<f32>,
+    ) -> Result<PatternWeights, String> {
+        if weights.len() != pattern_set.n_weights() {
+            return Err(format!(
+                "invalid number of weights: expected {}, actual {}",
+                pattern_set.n_weights(),
+                weights.len()
+            ));
+        }
+        Ok(PatternWeights {
+            pattern_set,
+            weights: Cow::Owned(weights),
+        })
+    }
+
+    /// Parse comma-separated weights of the standard pattern set such as
+    /// `python/data/parameters/0925.txt`.
+    pub fn parse(weights_str: &str) -> Result<PatternWeights, String> {
+        PatternWeights::create(parse_weights(weights_str)?)
+    }
+
+    pub fn parse_with_pattern_set(
+        pattern_set: PatternSet,
+        weights_str: &str,
+    ) -> Result<PatternWeights, String> {
+        PatternWeights::create_with_pattern_set(pattern_set, parse_weights(weights_str)?)
+    }
+
+    pub fn load(path: &str) -> Result<PatternWeights, String> {
+        let weights_str =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        PatternWeights::parse(&weights_str)
+    }
+
+    pub fn load_with_pattern_set(
+        pattern_set: PatternSet,
+        path: &str,
+    ) -> Result<PatternWeights, String> {
+        let weights_str =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        PatternWeights::parse_with_pattern_set(pattern_set, &weights_str)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let weights_str = self
+            .weights
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        fs::write(path, weights_str).map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    pub fn pattern_set(&self) -> &PatternSet {
+        &self.pattern_set
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn weights_mut(&mut self) -> &mut [f32] {
+        self.weights.to_mut()
+    }
+
+    /// Indices of the weights used by `score`, i.e. the features of a linear model.
+    pub fn feature_indices(&self, board: &Board, player: &Player) -> Vec<usize> {
+        self.pattern_set.feature_indices(board, player)
+    }
+
+    pub fn score(&self, board: &Board, player: &Player) -> f32 {
+        self.feature_indices(board, player)
+            .iter()
+            .map(|&i| self.weights[i])
+            .sum()
+    }
+
+    /// Break the pattern score of `board` down into the contribution of each pattern instance.
+    pub fn explain(&self, board: &Board, player: &Player) -> Vec<PatternContribution> {
+        let pattern_set = &self.pattern_set;
+        let n_kinds = pattern_set.definitions().len();
+
+        pattern_set
+            .instance_indices(board, player)
+            .iter()
+            .enumerate()
+            .map(|(i, &instance_index)| {
+                let i_kind = pattern_set.kind_of(i);
+                let definition = &pattern_set.definitions()[i_kind];
+                let (rotation, reflected) = Symmetry::describe(i / n_kinds);
+                PatternContribution {
+                    pattern_name: definition.name.clone(),
+                    rotation,
+                    reflected,
+                    instance_index,
+                    cells: decode_pattern_instance(instance_index, definition.cells.len()),
+                    weight: self.weights[instance_index as usize + pattern_set.offsets()[i_kind]],
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_weights(weights_str: &str) -> Result<Vec<f32>, String> {
+    weights_str
+        .split(',')
+        .map(|w| w.trim())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            w.parse::<f32>()
+                .map_err(|e| format!("invalid weight {:?}: {}", w, e))
+        })
+        .collect()
+}
+
+/// Pattern weights stored as `i16`, where the weight is `quantized weight * scale`.
+/// The score is summed up in integers, so that comparisons in the search are exact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedPatternWeights {
+    pattern_set: Cow<'static, PatternSet>,
+    weights: Cow<'static, [i16]>,
+    scale: f32,
+}
+
+impl Default for QuantizedPatternWeights {
+    #[cfg(not(feature = "quantized-weights"))]
+    fn default() -> QuantizedPatternWeights {
+        QuantizedPatternWeights::quantize(&PatternWeights::default())
+    }
+
+    #[cfg(feature = "quantized-weights")]
+    fn default() -> QuantizedPatternWeights {
+        QuantizedPatternWeights {
+            pattern_set: Cow::Borrowed(PatternSet::standard()),
+            weights: Cow::Borrowed(&PATTERN_INSTANCES_QUANTIZED),
+            scale: PATTERN_SCALE,
+        }
+    }
+}
+
+impl QuantizedPatternWeights {
+    /// Quantize with the scale mapping the largest absolute weight to `i16::MAX`.
+    pub fn quantize(weights: &PatternWeights) -> QuantizedPatternWeights {
+        let max_abs = weights
+            .weights()
+            .iter()
+            .fold(0.0f32, |acc, w| acc.max(w.abs()));
+        let scale = if max_abs > 0.0 {
+            max_abs / i16::MAX as f32
+        } else {
+            1.0
+        };
+        let quantized = weights
+            .weights()
+            .iter()
+            .map(|w| (w / scale).round() as i16)
+            .collect::<Vec<i16>>();
+        QuantizedPatternWeights {
+            pattern_set: weights.pattern_set.clone(),
+            weights: Cow::Owned(quantized),
+            scale,
+        }
+    }
+
+    pub fn dequantize(&self) -> PatternWeights {
+        PatternWeights {
+            pattern_set: self.pattern_set.clone(),
+            weights: Cow::Owned(
+                self.weights
+                    .iter()
+                    .map(|&w| w as f32 * self.scale)
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn weights(&self) -> &[i16] {
+        &self.weights
+    }
+
+    /// Pattern score in units of `scale`
+    pub fn score(&self, board: &Board, player: &Player) -> i32 {
+        self.pattern_set
+            .feature_indices(board, player)
+            .iter()
+            .map(|&i| self.weights[i] as i32)
+            .sum()
+    }
+
+    /// Source of `parameters_quantized.rs`, which is embedded with the `quantized-weights` feature.
+    pub fn to_rust_source(&self) -> String {
+        let weights = self
+            .weights
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        format!(
+            "#[rustfmt::skip]\n\
+             #[allow(clippy::module_inception)]\n\
+             pub mod parameters_quantized {{\n    \
+                 #[allow(dead_code)]\n    \
+                 pub const PATTERN_SCALE: f32 = {:?};\n    \
+                 #[allow(dead_code, clippy::large_const_arrays)]\n    \
+                 pub const PATTERN_INSTANCES_QUANTIZED: [i16; {}] = [{}];\n\
+             }}\n",
+            self.scale,
+            self.weights.len(),
+            weights
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternContribution {
+    pub pattern_name: String,
+    /// Rotation of the pattern in degrees (0, 90, 180 or 270)
+    pub rotation: u16,
+    /// Whether the pattern is reflected along the diagonal a1-h8 before the rotation
+    pub reflected: bool,
+    pub instance_index: u64,
+    /// Cells of the pattern instance in pattern order: `o` = current player, `x` = opponent
+    pub cells: String,
+    pub weight: f32,
+}
+
+impl fmt::Display for PatternContribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:>3}{} {:>6} {:<10} {:>9.3}",
+            self.pattern_name,
+            self.rotation,
+            if self.reflected { "r" } else { " " },
+            self.instance_index,
+            self.cells,
+            self.weight
+        )
+    }
+}
+
+pub fn decode_pattern_instance(instance_index: u64, n_cells: usize) -> String {
+    let mut n = instance_index;
+    let mut cells = String::with_capacity(n_cells);
+    for _ in 0..n_cells {
+        cells.push(match n % 3 {
+            0 => '-',
+            1 => 'o',
+            _ => 'x',
+        });
+        n /= 3;
+    }
+    cells
+}
+
+pub fn render_explanation(contributions: &[PatternContribution]) -> String {
+    let mut lines = vec![format!(
+        "{:<12} {:>4} {:>6} {:<10} {:>9}",
+        "pattern", "rot", "index", "cells", "weight"
+    )];
+    lines.extend(contributions.iter().map(|c| c.to_string()));
+    let total: f32 = contributions.iter().map(|c| c.weight).sum();
+    lines.push(format!(
+        "total = {:.3} (win probability {:.1}%)",
+        total,
+        100.0 * win_probability::win_probability(total, win_probability::DEFAULT_SCALE)
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::bitboard;
+    use crate::board::bitboard::pattern;
+    use crate::board::pattern_set::PatternSet;
+    use crate::board::Board;
+    use crate::board::Player;
+    use crate::pattern_weights::*;
+
+    fn fixture_board() -> Board {
+        Board::create_from_str(
+            "
+            - - - - - - - -
+            - - - - - - - -
+            - - - - o - - -
+            - - - o o - - -
+            - - - x o - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ",
+        )
+    }
+
+    fn fixture_weights() -> PatternWeights {
+        let weights = (0..pattern::N_PATTERN_INSTANCES)
+            .map(|i| (i % 7) as f32 - 3.0)
+            .collect();
+        PatternWeights::create(weights).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(PatternWeights::parse("0.1,0.2,").is_err());
+
+        let weights_str = vec!["0.500"; pattern::N_PATTERN_INSTANCES].join(",") + ",";
+        let weights = PatternWeights::parse(&weights_str).unwrap();
+        assert_eq!(weights.weights()[0], 0.5);
+        assert_eq!(weights.weights().len(), pattern::N_PATTERN_INSTANCES);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let weights = fixture_weights();
+        let path = std::env::temp_dir().join("reversi-pattern-weights-save-and-load.txt");
+        let path = path.to_str().unwrap();
+        weights.save(path).unwrap();
+        assert_eq!(PatternWeights::load(path).unwrap(), weights);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn default_weights_match_calculate_pattern_score() {
+        let board = fixture_board();
+        let indices = bitboard::extract_pattern_instance_indices(&board, &Player::First);
+        assert_eq!(
+            PatternWeights::default().score(&board, &Player::First),
+            Board::calculate_pattern_score(indices)
+        );
+    }
+
+    #[cfg(feature = "quantized-weights")]
+    #[test]
+    fn default_weights_are_dequantized_once() {
+        assert_eq!(
+            PatternWeights::default().weights().as_ptr(),
+            PatternWeights::default().weights().as_ptr()
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let weights = fixture_weights();
+        let contributions = weights.explain(&fixture_board(), &Player::Second);
+        assert_eq!(contributions.len(), pattern::N_PATTERNS);
+
+        // rotate: +0, hor./vert. 3 = [16, ..., 23]
+        let contribution = &contributions[6];
+        assert_eq!(contribution.pattern_name, "hor./vert.3");
+        assert_eq!(contribution.rotation, 0);
+        assert_eq!(contribution.cells, "----x---");
+        assert_eq!(contribution.instance_index, 2 * 3u64.pow(4));
+        assert_eq!(
+            contribution.weight,
+            weights.weights()[2 * 3usize.pow(4) + PatternSet::standard().offsets()[6]]
+        );
+
+        // rotate: +270, diag8
+        assert_eq!(contributions[37].pattern_name, "diag8");
+        assert_eq!(contributions[37].rotation, 270);
+
+        let total: f32 = contributions.iter().map(|c| c.weight).sum();
+        assert_eq!(weights.score(&fixture_board(), &Player::Second), total);
+    }
+
+    #[test]
+    fn custom_pattern_set() {
+        let pattern_set = PatternSet::parse("corner: a1 b1\nx: b2").unwrap();
+        assert!(
+            PatternWeights::create_with_pattern_set(pattern_set.clone(), vec![0.0; 9]).is_err()
+        );
+
+        let mut weights = PatternWeights::zeros(pattern_set);
+        assert_eq!(weights.weights().len(), 9 + 3);
+        // `o` on the corner a1, `x` on the X-squares b2 and g7
+        weights.weights_mut()[1] = 1.0;
+        weights.weights_mut()[9 + 2] = -0.5;
+        assert_eq!(weights.score(&fixture_board(), &Player::First), 0.0);
+
+        let board = Board::create_from_str(
+            "
+            o - - - - - - -
+            - x - - - - - -
+            - - - - - - - -
+            - - - o x - - -
+            - - - x o - - -
+            - - - - - - - -
+            - - - - - - x -
+            - - - - - - - -
+        ",
+        );
+        assert_eq!(weights.score(&board, &Player::First), 1.0 - 0.5 - 0.5);
+        assert_eq!(
+            weights.explain(&board, &Player::First)[0].pattern_name,
+            "corner"
+        );
+    }
+
+    #[test]
+    fn quantize() {
+        let weights = fixture_weights();
+        let quantized = QuantizedPatternWeights::quantize(&weights);
+        assert_eq!(quantized.scale(), 3.0 / i16::MAX as f32);
+
+        let dequantized = quantized.dequantize();
+        for (w, d) in weights.weights().iter().zip(dequantized.weights().iter()) {
+            assert!((w - d).abs() <= quantized.scale() / 2.0);
+        }
+
+        let board = fixture_board();
+        let score = quantized.score(&board, &Player::First) as f32 * quantized.scale();
+        assert!((score - weights.score(&board, &Player::First)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn quantized_rust_source() {
+        let source = QuantizedPatternWeights::quantize(&fixture_weights()).to_rust_source();
+        assert!(source.starts_with("#[rustfmt::skip]\n#[allow(clippy::module_inception)]\n"));
+        assert!(source.contains("pub const PATTERN_INSTANCES_QUANTIZED: [i16; 167265] = [-32767,"));
+    }
+
+    #[test]
+    fn decode_pattern_instance() {
+        let index = 1 + 2 * 3u64.pow(2) + 3u64.pow(3);
+        assert_eq!(
+            crate::pattern_weights::decode_pattern_instance(index, 5),
+            "o-xo-"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::strategy::error::StrategyError;
+use crate::strategy::Strategy;
+
+/// Leaves evaluated by each step of `Game::ponder_step` and `PonderThread`, which is a few
+/// milliseconds of search. Since each step searches again from the transposition table, a much
+/// smaller budget may never finish a deep search.
+pub const STEP_N_LEAVES: usize = 10000;
+
+/// Searches the positions after the possible replies of the opponent while the opponent is
+/// thinking, by bounded steps (see `Strategy::ponder`), so that the search of the actual reply
+/// finds the state of the strategy filled, e.g. its transposition table.
+#[derive(Clone, Debug)]
+pub struct Ponderer {
+    player: Player,
+    /// Positions to search, the expected reply last so that it is searched first
+    pending: Vec<(Board, usize)>,
+}
+
+impl Ponderer {
+    /// Ponder for `player` while its opponent is to move on `board`, which is the `i_step`-th
+    /// move. `expected_reply` (see `Strategy::expected_reply`) is searched first.
+    pub fn create(
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        expected_reply: Option<Square>,
+    ) -> Ponderer {
+        let opponent = player.opponent();
+        let mut replies = board.get_all_legal_moves(&opponent);
+        replies.sort_by_key(|&reply| Square::from_bitboard(reply) == expected_reply);
+        let mut pending: Vec<(Board, usize)> = replies
+            .into_iter()
+            .map(|reply| {
+                let mut child = board.clone();
+                child.put_and_reverse(&opponent, reply);
+                (child, i_step + 1)
+            })
+            .collect();
+        if pending.is_empty() {
+            // the opponent passes
+            pending.push((board.clone(), i_step));
+        }
+        pending.retain(|(child, _)| child.legal_moves(player) != 0);
+        Ponderer {
+            player: player.clone(),
+            pending,
+        }
+    }
+
+    /// Continue the search of the next pending position by about `max_n_leaves` evaluated
+    /// leaves, and return whether positions remain. Pondering stops when the strategy does not
+    /// support it.
+    pub fn step(
+        &mut self,
+        strategy: &mut dyn Strategy,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        if let Some((board, i_step)) = self.pending.last().cloned() {
+            match strategy.ponder(&board, &self.player, i_step, max_n_leaves) {
+                Ok(true) => {
+                    self.pending.pop();
+                }
+                Ok(false) => (),
+                Err(StrategyError::PonderingNotSupported) => self.stop(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(!self.pending.is_empty())
+    }
+
+    /// Stop searching, e.g. when the opponent has moved
+    pub fn stop(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn n_pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Pondering on a background thread, which owns the strategy until `stop`
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PonderThread<S: Strategy + Send + 'static> {
+    stop_requested: Arc<AtomicBool>,
+    handle: thread::JoinHandle<(S, Ponderer, Result<(), StrategyError>)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S: Strategy + Send + 'static> PonderThread<S> {
+    pub fn spawn(mut strategy: S, mut ponderer: Ponderer) -> PonderThread<S> {
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let stop = stop_requested.clone();
+        let handle = thread::spawn(move || {
+            let mut result = Ok(());
+            while !stop.load(Ordering::Relaxed) {
+                match ponderer.step(&mut strategy, STEP_N_LEAVES) {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+            (strategy, ponderer, result)
+        });
+        PonderThread {
+            stop_requested,
+            handle,
+        }
+    }
+
+    /// Whether all the positions have been searched
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Give back the strategy. The current step is finished before stopping.
+    pub fn stop(self) -> Result<(S, Ponderer), StrategyError> {
+        self.stop_requested.store(true, Ordering::Relaxed);
+        let (strategy, mut ponderer, result) = self
+            .handle
+            .join()
+            .map_err(|_| StrategyError::Other(String::from("pondering thread panicked")))?;
+        result?;
+        ponderer.stop();
+        Ok((strategy, ponderer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board;
+    use crate::ponder::*;
+    use crate::rng::Rng;
+    use crate::strategy::baseline::GreedyStrategy;
+    use crate::strategy::config::ConfiguredStrategy;
+    use crate::strategy::config::EvaluatorType;
+    use crate::strategy::config::StrategyConfig;
+    use crate::strategy::NumdiskLookaheadStrategy;
+
+    fn board_after_f5() -> Board {
+        let mut board = board::new_board();
+        board.put_and_reverse(&Player::First, 1 << 37); // f5
+        board
+    }
+
+    #[test]
+    fn ponder_all_replies() {
+        // the engine plays white and black is to move after f5 d6
+        let mut board = board_after_f5();
+        board.put_and_reverse(&Player::Second, 1 << 43); // d6
+        let c5 = Square::from_coord("c5").unwrap();
+        let mut ponderer = Ponderer::create(&board, &Player::Second, 2, Some(c5));
+        assert_eq!(ponderer.n_pending(), 5);
+
+        let mut strategy = NumdiskLookaheadStrategy::default();
+        let mut n_steps = 1;
+        while ponderer.step(&mut strategy, STEP_N_LEAVES).unwrap() {
+            n_steps += 1;
+        }
+        // the positions need several steps each
+        assert!(n_steps > 5);
+
+        // the search of the actual reply continues from the pondered entries, and may choose
+        // another move of the same score
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, 1 << 34); // c5
+        let pondered = strategy.get_next_move(&child, &Player::Second, 3).unwrap();
+        let searched = NumdiskLookaheadStrategy::default()
+            .get_next_move(&child, &Player::Second, 3)
+            .unwrap();
+        assert_eq!(pondered.score, searched.score);
+        assert!(pondered.n_nodes < searched.n_nodes);
+    }
+
+    #[test]
+    fn expected_reply_first() {
+        let board = board_after_f5();
+        let f6 = Square::from_coord("f6").unwrap();
+        let mut ponderer = Ponderer::create(&board, &Player::First, 1, Some(f6));
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::Second, 1 << 45);
+        assert_eq!(ponderer.pending.last().unwrap().0, child);
+        let mut strategy = NumdiskLookaheadStrategy::default();
+        while ponderer.n_pending() == 3 {
+            ponderer.step(&mut strategy, STEP_N_LEAVES).unwrap();
+        }
+        assert_eq!(ponderer.n_pending(), 2);
+        ponderer.stop();
+        assert_eq!(ponderer.n_pending(), 0);
+    }
+
+    #[test]
+    fn resume_bounded_steps() {
+        let board = board_after_f5();
+        let mut ponderer = Ponderer::create(&board, &Player::First, 1, None);
+        let config = StrategyConfig {
+            depth_schedule: vec![(0, 4)],
+            evaluator: EvaluatorType::Heuristic,
+            ..StrategyConfig::default()
+        };
+        let mut strategy = ConfiguredStrategy::create(config, Rng::create(0));
+        let mut n_steps = 1;
+        while ponderer.step(&mut strategy, 100).unwrap() {
+            n_steps += 1;
+        }
+        // each of the 3 replies needs more than one step
+        assert!(n_steps > 3);
+    }
+
+    #[test]
+    fn stop_without_pondering_support() {
+        let board = board_after_f5();
+        let mut ponderer = Ponderer::create(&board, &Player::First, 1, None);
+        assert!(!ponderer
+            .step(&mut GreedyStrategy {}, STEP_N_LEAVES)
+            .unwrap());
+        assert_eq!(ponderer.n_pending(), 0);
+    }
+
+    #[test]
+    fn ponder_on_thread() {
+        let board = board_after_f5();
+        let ponderer = Ponderer::create(&board, &Player::First, 1, None);
+        let thread = PonderThread::spawn(NumdiskLookaheadStrategy::default(), ponderer);
+        while !thread.is_finished() {
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let (_strategy, ponderer) = thread.stop().unwrap();
+        assert_eq!(ponderer.n_pending(), 0);
+    }
+}
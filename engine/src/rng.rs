@@ -0,0 +1,58 @@
+/// Small seedable pseudo random number generator (xorshift64*), which works both natively and
+/// in wasm without depending on the platform entropy source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn create(seed: u64) -> Rng {
+        const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+        // the state of xorshift must not be zero
+        let state = seed ^ GOLDEN_GAMMA;
+        Rng {
+            state: if state == 0 { GOLDEN_GAMMA } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform random number in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform random integer in [0, n)
+    pub fn next_usize(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut rng1 = Rng::create(42);
+        let mut rng2 = Rng::create(42);
+        for _ in 0..10 {
+            assert_eq!(rng1.next_u64(), rng2.next_u64());
+        }
+        assert_ne!(Rng::create(1).next_u64(), Rng::create(2).next_u64());
+    }
+
+    #[test]
+    fn next_f32_range() {
+        let mut rng = Rng::create(0);
+        for _ in 0..1000 {
+            let x = rng.next_f32();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+}
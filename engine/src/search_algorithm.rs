@@ -1,4 +1,13 @@
 mod alphabeta;
 mod base;
+mod result;
+mod state;
 
 pub use alphabeta::AlphaBeta;
+pub use base::GameTreeLeaf;
+pub use base::Score;
+pub use base::SearchAlgorithm;
+pub use result::ResultSource;
+pub use result::SearchResult;
+pub use state::Bound;
+pub use state::SearchState;
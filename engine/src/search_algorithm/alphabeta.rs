@@ -1,58 +1,252 @@
+use crate::board::count_bits;
 use crate::board::Board;
 use crate::board::Player;
+use crate::board::Square;
 use crate::search_algorithm::base::*;
-
-pub struct AlphaBeta {
+use crate::search_algorithm::result::ResultSource;
+use crate::search_algorithm::result::SearchResult;
+use crate::search_algorithm::state::Bound;
+use crate::search_algorithm::state::SearchState;
+use crate::search_algorithm::state::TranspositionEntry;
+use crate::utils;
+
+type EvaluateBoardFunc<S> = Box<dyn Fn(&Board, &Player) -> S>;
+
+/// Parity move ordering is used when the number of empty squares is this value or less
+const PARITY_ORDERING_EMPTIES: u64 = 16;
+/// The deadline (see `set_deadline`) is checked every this number of evaluated leaves
+const DEADLINE_CHECK_N_LEAVES: usize = 1024;
+
+pub struct AlphaBeta<S: Score = f32> {
+    /// The search is aborted when more leaves than this are evaluated (see `search`)
     max_n_leaves: usize,
     n_evaluated_leaves: usize,
-    best_leaves: Vec<GameTreeLeaf>,
-    evaluate_board_func: fn(&Board, &Player) -> f32,
+    /// The search is also aborted after this time (see `utils::now_millis`)
+    deadline_ms: Option<f64>,
+    deadline_reached: bool,
+    best_leaves: Vec<GameTreeLeaf<S>>,
+    evaluate_board_func: EvaluateBoardFunc<S>,
+    stability_cutoff: bool,
+    parity_ordering: bool,
+    logging: bool,
+    state: SearchState<S>,
+    /// Nodes visited by the last search
+    n_nodes: u64,
 }
 
-impl SearchAlgorithm for AlphaBeta {
+impl<S: Score> SearchAlgorithm<S> for AlphaBeta<S> {
     fn n_evaluated_leaves(&self) -> usize {
         self.n_evaluated_leaves
     }
 
-    fn best_leaves(&self) -> Vec<GameTreeLeaf> {
+    fn best_leaves(&self) -> Vec<GameTreeLeaf<S>> {
         self.best_leaves.clone()
     }
 
     fn increment_n_evaluated_leaves(&mut self) {
         self.n_evaluated_leaves += 1;
+        if let Some(deadline_ms) = self.deadline_ms
+            && self
+                .n_evaluated_leaves
+                .is_multiple_of(DEADLINE_CHECK_N_LEAVES)
+            && utils::now_millis() > deadline_ms
+        {
+            self.deadline_reached = true;
+        }
     }
 
-    fn evaluate_board(&self, board: &Board, player: &Player) -> f32 {
-        let evaluate = self.evaluate_board_func;
-        evaluate(board, player)
+    fn evaluate_board(&self, board: &Board, player: &Player) -> S {
+        (self.evaluate_board_func)(board, player)
     }
 }
 
-impl AlphaBeta {
+impl<S: Score> AlphaBeta<S> {
     pub fn create(
         max_n_leaves: usize,
-        evaluate_board_func: fn(&Board, &Player) -> f32,
-    ) -> AlphaBeta {
+        evaluate_board_func: impl Fn(&Board, &Player) -> S + 'static,
+    ) -> AlphaBeta<S> {
         AlphaBeta {
             max_n_leaves,
             n_evaluated_leaves: 0,
+            deadline_ms: None,
+            deadline_reached: false,
             best_leaves: vec![],
-            evaluate_board_func,
+            evaluate_board_func: Box::new(evaluate_board_func),
+            stability_cutoff: false,
+            parity_ordering: false,
+            logging: true,
+            state: SearchState::default(),
+            n_nodes: 0,
+        }
+    }
+
+    /// Continue from the state of previous searches with the same evaluation function, e.g. the
+    /// previous move of the game.
+    pub fn set_state(&mut self, state: SearchState<S>) {
+        self.state = state;
+    }
+
+    /// State after the search, to be given to the next search by `set_state`
+    pub fn take_state(&mut self) -> SearchState<S> {
+        std::mem::take(&mut self.state)
+    }
+
+    /// Abort the searches after `deadline_ms` (see `utils::now_millis`) as well as by
+    /// `max_n_leaves`, e.g. for a time budget
+    pub fn set_deadline(&mut self, deadline_ms: f64) {
+        self.deadline_ms = Some(deadline_ms);
+    }
+
+    /// Stop printing the search results, e.g. when playing many games in a match.
+    pub fn disable_logging(&mut self) {
+        self.logging = false;
+    }
+
+    /// Prune nodes whose upper bound computed from the stable discs of the opponent is not
+    /// better than alpha, when the remaining depth reaches the end of the game.
+    /// This is valid only when the evaluation function is the disc difference (`score_numdisk`).
+    pub fn enable_stability_cutoff(&mut self) {
+        self.stability_cutoff = true;
+    }
+
+    /// In the endgame, search moves into odd empty regions first, since they are more likely to
+    /// keep the last move of the region (and thus to be the best move).
+    pub fn enable_parity_ordering(&mut self) {
+        self.parity_ordering = true;
+    }
+
+    /// The best move stored in the transposition table first, then the move of the principal
+    /// variation of the last search, then the moves into odd regions (see
+    /// `enable_parity_ordering`), then the moves with more history cutoffs.
+    fn order_moves(
+        &self,
+        board: &Board,
+        player: &Player,
+        tt_move: Option<u64>,
+        pv_move: Option<u64>,
+        legal_moves: &mut [u64],
+    ) {
+        let odd_regions = if self.parity_ordering
+            && count_bits(!(board.first() | board.second())) <= PARITY_ORDERING_EMPTIES
+        {
+            board
+                .empty_regions()
+                .iter()
+                .filter(|region| region.is_odd())
+                .fold(0, |acc, region| acc | region.squares)
+        } else {
+            !0
+        };
+        legal_moves.sort_by_key(|&legal_move| {
+            (
+                tt_move != Some(legal_move),
+                pv_move != Some(legal_move),
+                odd_regions & legal_move == 0,
+                std::cmp::Reverse(self.state.history_score(player, legal_move)),
+            )
+        });
+    }
+
+    /// Ply of the child reached by `next_move` in the principal variation of the last search,
+    /// when the path to the node at `pv_ply` follows it.
+    fn child_pv_ply(&self, pv_ply: Option<usize>, next_move: Option<u64>) -> Option<usize> {
+        pv_ply
+            .filter(|&ply| self.state.principal_variation().get(ply) == Some(&next_move))
+            .map(|ply| ply + 1)
+    }
+
+    /// Move of the principal variation of the last search at the node at `pv_ply`
+    fn pv_move(&self, pv_ply: Option<usize>) -> Option<u64> {
+        pv_ply.and_then(|ply| self.state.principal_variation().get(ply).copied().flatten())
+    }
+
+    /// Principal variation from `board` stored in the transposition table, by following the best
+    /// moves of the exact entries for at most `remaining_depth` moves. The moves are in reverse
+    /// order as the moves returned by `search_inner`.
+    fn transposition_line(
+        &self,
+        board: &Board,
+        player: &Player,
+        remaining_depth: u64,
+    ) -> Vec<Option<u64>> {
+        let mut board = board.clone();
+        let mut player = player.clone();
+        let mut line = vec![];
+        let mut n_moves = 0;
+        while n_moves < remaining_depth {
+            if board.legal_moves(&player) == 0 {
+                if board.legal_moves(&player.opponent()) == 0 {
+                    break;
+                }
+                line.push(None);
+                player = player.opponent();
+                continue;
+            }
+            let best_move = match self.state.probe(&board, &player) {
+                Some(TranspositionEntry {
+                    bound: Bound::Exact,
+                    best_move: Some(best_move),
+                    ..
+                }) => *best_move,
+                _ => break,
+            };
+            line.push(Some(best_move));
+            board.put_and_reverse(&player, best_move);
+            player = player.opponent();
+            n_moves += 1;
+        }
+        // a pass at the end of the line is not followed by a stored move
+        while line.last() == Some(&None) {
+            line.pop();
         }
+        line.reverse();
+        line
     }
 
-    /// This function assume the next turn is the first player (black).
-    pub fn search(&mut self, initial_board: &Board, depth: u64) -> Option<(Option<u64>, f32)> {
-        let legal_moves = initial_board.get_all_legal_moves(&Player::First);
+    /// Whether more than `max_n_leaves` leaves have been evaluated by the last search or its
+    /// deadline has passed (see `set_deadline`), so that its result is only of the root moves
+    /// searched before
+    pub fn is_aborted(&self) -> bool {
+        self.n_evaluated_leaves > self.max_n_leaves || self.deadline_reached
+    }
+
+    /// This function assume the next turn is the first player (black). The scores of the root
+    /// moves are exact since each of them is searched with the full window.
+    ///
+    /// When the search is aborted (see `is_aborted`), the result is the best of
+    /// the root moves searched so far, whose score is a lower bound, or `None` when no root move
+    /// has been searched. The entries of the finished subtrees are kept in the state, so that the
+    /// same search continues from them.
+    pub fn search(&mut self, initial_board: &Board, depth: u64) -> Option<SearchResult<S>> {
+        let started_at = utils::now_millis();
+        self.n_nodes = 0;
+        self.n_evaluated_leaves = 0;
+        self.deadline_reached = false;
+        self.best_leaves.clear();
+        self.state.age_history();
+        let mut legal_moves = initial_board.get_all_legal_moves(&Player::First);
+        // the root is searched with the principal variation of the last search first, e.g. the
+        // previous depth of iterative deepening
+        self.order_moves(
+            initial_board,
+            &Player::First,
+            None,
+            self.pv_move(Some(0)),
+            &mut legal_moves,
+        );
         let search_results = if legal_moves.is_empty() {
             let (child_score, mut leaf_moves) = self.search_inner(
                 None,
+                self.child_pv_ply(Some(0), None),
                 &Player::Second,
                 initial_board,
                 depth,
-                -f32::MAX,
-                f32::MAX,
+                -S::MAX,
+                S::MAX,
             );
+            if self.is_aborted() {
+                return None;
+            }
             leaf_moves.push(None);
             leaf_moves.reverse();
 
@@ -62,19 +256,23 @@ impl AlphaBeta {
             Some((None, -child_score))
         } else {
             let mut node_max_score: Option<u64> = None;
-            let mut max_score_opt: Option<f32> = None;
+            let mut max_score_opt: Option<S> = None;
             for legal_move in legal_moves {
                 let mut board = initial_board.clone();
                 board.put_and_reverse(&Player::First, legal_move);
 
                 let (child_score, mut leaf_moves) = self.search_inner(
                     Some(legal_move),
+                    self.child_pv_ply(Some(0), Some(legal_move)),
                     &Player::Second,
                     &board,
                     depth,
-                    -f32::MAX,
-                    f32::MAX,
+                    -S::MAX,
+                    S::MAX,
                 );
+                if self.is_aborted() {
+                    break;
+                }
                 leaf_moves.push(Some(legal_move));
                 leaf_moves.reverse();
 
@@ -102,26 +300,99 @@ impl AlphaBeta {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        self.print_search_results();
+        if let Some(best_leaf) = self.best_leaves.first() {
+            self.state.set_principal_variation(best_leaf.moves());
+        }
+        if self.logging {
+            self.print_search_results();
+        }
 
-        search_results
+        let principal_variation = self.best_leaves.first().map_or(vec![], |leaf| {
+            leaf.moves()
+                .iter()
+                .map(|m| m.and_then(Square::from_bitboard))
+                .collect()
+        });
+        let bound = if self.is_aborted() {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        search_results.map(|(best_move, score)| SearchResult {
+            best_move: best_move.and_then(Square::from_bitboard),
+            score,
+            bound,
+            principal_variation,
+            depth,
+            n_nodes: self.n_nodes,
+            elapsed_ms: utils::now_millis() - started_at,
+            source: ResultSource::Search,
+        })
     }
 
+    /// `pv_ply` is the ply of the node in the principal variation of the last search, while the
+    /// path from the root follows it.
+    #[allow(clippy::too_many_arguments)]
     fn search_inner(
         &mut self,
         last_move: Option<u64>,
+        pv_ply: Option<usize>,
         player: &Player,
         board: &Board,
         remaining_depth: u64,
-        alpha: f32,
-        beta: f32,
-    ) -> (f32, Vec<Option<u64>>) {
-        if board.is_full() || remaining_depth == 0 || self.n_evaluated_leaves > self.max_n_leaves {
+        alpha: S,
+        beta: S,
+    ) -> (S, Vec<Option<u64>>) {
+        self.n_nodes += 1;
+        if self.is_aborted() {
+            return (alpha, vec![]);
+        }
+        if board.is_full() || remaining_depth == 0 {
+            self.increment_n_evaluated_leaves();
             let score = self.evaluate_board(board, player);
             return (score, vec![]);
         }
 
-        let legal_moves = board.get_all_legal_moves(player);
+        if self.stability_cutoff && remaining_depth >= count_bits(!(board.first() | board.second()))
+        {
+            let (first_stable, second_stable) = board.stable_discs();
+            let opponent_stable = match player {
+                Player::First => second_stable,
+                Player::Second => first_stable,
+            };
+            let upper_bound = S::from_disc_difference(64 - 2 * count_bits(opponent_stable) as i32);
+            if upper_bound <= alpha {
+                return (upper_bound, vec![]);
+            }
+        }
+
+        let n_empties = count_bits(!(board.first() | board.second()));
+        // the search reaches the end of the game when the remaining depth is the number of empty
+        // squares or more, since passes do not consume the depth
+        let tt_depth = remaining_depth.min(n_empties);
+        let tt_entry = self.state.probe(board, player).copied();
+        if let Some(entry) = tt_entry
+            && entry.depth >= tt_depth
+        {
+            match entry.bound {
+                Bound::Exact => {
+                    let line = self.transposition_line(board, player, remaining_depth);
+                    return (entry.score, line);
+                }
+                Bound::Lower if entry.score >= beta => return (entry.score, vec![entry.best_move]),
+                Bound::Upper if entry.score <= alpha => return (entry.score, vec![]),
+                _ => (),
+            }
+        }
+
+        let mut legal_moves = board.get_all_legal_moves(player);
+        self.order_moves(
+            board,
+            player,
+            tt_entry.and_then(|entry| entry.best_move),
+            self.pv_move(pv_ply),
+            &mut legal_moves,
+        );
         let mut best_current_move: Option<u64> = None;
         let mut best_leaf_moves = vec![];
 
@@ -132,12 +403,16 @@ impl AlphaBeta {
                 // when there is no legal next moves and current move is non-empty, then create empty node.
                 let (child_score, current_best_move) = self.search_inner(
                     None,
+                    self.child_pv_ply(pv_ply, None),
                     &player.opponent(),
                     board,
                     remaining_depth, // NOTE: do not consume depth when passing
                     -beta,
                     -alpha,
                 );
+                if self.is_aborted() {
+                    return (alpha, vec![]);
+                }
 
                 if alpha < -child_score {
                     alpha = -child_score;
@@ -146,6 +421,7 @@ impl AlphaBeta {
                 }
             } else {
                 // when there is no legal next moves and next move is empty, then it is a leaf node
+                self.increment_n_evaluated_leaves();
                 alpha = self.evaluate_board(board, player);
             }
         } else {
@@ -156,21 +432,43 @@ impl AlphaBeta {
 
                 let (child_score, current_moves) = self.search_inner(
                     Some(*legal_move),
+                    self.child_pv_ply(pv_ply, Some(*legal_move)),
                     &player.opponent(),
                     &next_board,
                     remaining_depth - 1,
                     -beta,
                     -alpha,
                 );
+                if self.is_aborted() {
+                    return (alpha, vec![]);
+                }
                 if alpha < -child_score {
                     alpha = -child_score;
                     best_current_move = Some(*legal_move);
                     best_leaf_moves = current_moves;
                 }
                 if alpha >= beta {
+                    self.state.add_cutoff(player, *legal_move, remaining_depth);
                     break;
                 }
             }
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if best_current_move.is_none() {
+                Bound::Upper
+            } else {
+                Bound::Exact
+            };
+            self.state.store(
+                board,
+                player,
+                TranspositionEntry {
+                    depth: tt_depth,
+                    score: alpha,
+                    bound,
+                    best_move: best_current_move,
+                },
+            );
         };
         best_leaf_moves.push(best_current_move);
         (alpha, best_leaf_moves)
@@ -182,22 +480,7 @@ mod tests {
     use crate::board::bitboard;
     use crate::board::bitboard::put_position_to_coord;
     use crate::search_algorithm::alphabeta::*;
-
-    fn fixture_board() -> Board {
-        // Puzzle 99 in Brian Rose, "Othello: A Minute to Learn...A Lifetime to Master"
-        Board::create_from_str(
-            "
-            - x x x x x - o
-            - - x x x x x o
-            o x x x o x o o
-            o o o x x x o o
-            o x x x x x o o
-            o x x x x x o o
-            o - x x x x - o
-            - x x x x x x -
-            ",
-        )
-    }
+    use crate::test_utils::fixture_board;
 
     #[test]
     fn create() {
@@ -239,8 +522,8 @@ mod tests {
         let search_result = alphabeta.search(&board, 5);
         assert!(search_result.is_some());
         let search_result = search_result.unwrap();
-        assert!(search_result.0.is_none());
-        let actual_best_score = search_result.1;
+        assert!(search_result.best_move.is_none());
+        let actual_best_score = search_result.score;
         assert_eq!(actual_best_score, -2.0); // 33-31
         let actual_best_moves = alphabeta.best_leaves[0]
             .moves()
@@ -274,9 +557,9 @@ mod tests {
         let search_result = alphabeta.search(&reversed_board, 5);
         assert!(search_result.is_some());
         let search_result = search_result.unwrap();
-        assert!(search_result.0.is_some());
-        let actual_best_move = search_result.0;
-        let actual_best_score = search_result.1;
+        assert!(search_result.best_move.is_some());
+        let actual_best_move = search_result.best_move.map(|m| m.bitboard());
+        let actual_best_score = search_result.score;
         assert_eq!(
             bitboard::put_position_to_coord(actual_best_move),
             Ok("a8".to_string())
@@ -290,6 +573,47 @@ mod tests {
         assert_eq!(actual_best_moves, vec!["a8", "b8", "a7"]);
     }
 
+    #[test]
+    fn search_case_puzzle99_stability_cutoff() {
+        let mut alphabeta = AlphaBeta::create(10000, |board: &Board, player: &Player| -> f32 {
+            board.score_numdisk(player)
+        });
+        alphabeta.enable_stability_cutoff();
+        let search_result = alphabeta.search(&fixture_board(), 9).unwrap();
+        assert_eq!(search_result.score, 38.0);
+        assert_eq!(
+            bitboard::put_position_to_coord(search_result.best_move.map(|m| m.bitboard())),
+            Ok("g1".to_string())
+        );
+    }
+
+    #[test]
+    fn search_case_puzzle99_integer_score() {
+        let mut alphabeta = AlphaBeta::create(10000, |board: &Board, player: &Player| -> i32 {
+            board.score_numdisk(player) as i32
+        });
+        let search_result = alphabeta.search(&fixture_board(), 9).unwrap();
+        assert_eq!(search_result.score, 38);
+        assert_eq!(
+            bitboard::put_position_to_coord(search_result.best_move.map(|m| m.bitboard())),
+            Ok("g1".to_string())
+        );
+    }
+
+    #[test]
+    fn search_case_puzzle99_parity_ordering() {
+        let mut alphabeta = AlphaBeta::create(10000, |board: &Board, player: &Player| -> f32 {
+            board.score_numdisk(player)
+        });
+        alphabeta.enable_parity_ordering();
+        let search_result = alphabeta.search(&fixture_board(), 9).unwrap();
+        assert_eq!(search_result.score, 38.0);
+        assert_eq!(
+            bitboard::put_position_to_coord(search_result.best_move.map(|m| m.bitboard())),
+            Ok("g1".to_string())
+        );
+    }
+
     #[test]
     fn search_case_puzzle99() {
         let mut alphabeta = AlphaBeta::create(10000, |board: &Board, player: &Player| -> f32 {
@@ -298,8 +622,8 @@ mod tests {
         let search_result = alphabeta.search(&fixture_board(), 9);
         assert!(search_result.is_some());
         let search_result = search_result.unwrap();
-        let actual_best_move = search_result.0;
-        let actual_best_score = search_result.1;
+        let actual_best_move = search_result.best_move.map(|m| m.bitboard());
+        let actual_best_score = search_result.score;
 
         // NOTE: there are other best moves that have the same score
         let expected_moves = vec![
@@ -318,4 +642,169 @@ mod tests {
             .collect::<Vec<String>>();
         assert_eq!(actual_best_moves, expected_moves);
     }
+
+    #[test]
+    fn search_with_state() {
+        let create = || {
+            let mut alphabeta = AlphaBeta::create(usize::MAX, |board: &Board, player: &Player| {
+                board.score_numdisk(player)
+            });
+            alphabeta.disable_logging();
+            alphabeta
+        };
+        let mut alphabeta = create();
+        let first_result = alphabeta.search(&fixture_board(), 9);
+        let state = alphabeta.take_state();
+        assert!(state.n_entries() > 0);
+        assert_eq!(
+            put_position_to_coord(state.principal_variation()[0]),
+            Ok("g1".to_string())
+        );
+
+        // the stored entries give the same result
+        let mut alphabeta = create();
+        alphabeta.set_state(state);
+        let second_result = alphabeta.search(&fixture_board(), 9).unwrap();
+        let first_result = first_result.unwrap();
+        assert_eq!(
+            (second_result.best_move, second_result.score),
+            (first_result.best_move, first_result.score)
+        );
+        assert_eq!(first_result.put_position(), 1 << 6);
+        assert_eq!(first_result.score, 38.0);
+        assert!(second_result.n_nodes < first_result.n_nodes);
+        // the principal variation is followed in the transposition table
+        assert_eq!(
+            second_result.principal_variation,
+            first_result.principal_variation
+        );
+    }
+
+    #[test]
+    fn best_leaves_of_last_search() {
+        let mut alphabeta = AlphaBeta::create(usize::MAX, |board: &Board, player: &Player| {
+            board.score_numdisk(player)
+        });
+        alphabeta.disable_logging();
+        let board = fixture_board();
+        let n_legal_moves = board.get_all_legal_moves(&Player::First).len();
+        for depth in [1, 2] {
+            alphabeta.search(&board, depth).unwrap();
+            assert_eq!(alphabeta.best_leaves().len(), n_legal_moves);
+        }
+    }
+
+    #[test]
+    fn search_aborted_by_deadline() {
+        let mut alphabeta = AlphaBeta::create(usize::MAX, |board: &Board, player: &Player| {
+            board.score_numdisk(player)
+        });
+        alphabeta.disable_logging();
+        alphabeta.set_deadline(0.0);
+        alphabeta.search(&crate::board::new_board(), 8);
+        assert!(alphabeta.is_aborted());
+        assert_eq!(alphabeta.n_evaluated_leaves(), DEADLINE_CHECK_N_LEAVES);
+    }
+
+    #[test]
+    fn search_aborted_by_max_n_leaves() {
+        let create = |max_n_leaves| {
+            let mut alphabeta =
+                AlphaBeta::create(max_n_leaves, |board: &Board, player: &Player| {
+                    board.score_numdisk(player)
+                });
+            alphabeta.disable_logging();
+            alphabeta
+        };
+        let mut alphabeta = create(10);
+        assert!(alphabeta.search(&fixture_board(), 9).is_none());
+        assert!(alphabeta.is_aborted());
+        assert_eq!(alphabeta.n_evaluated_leaves(), 11);
+
+        // the next searches continue from the entries of the finished subtrees, and the best of
+        // the root moves searched so far is kept
+        let mut state = alphabeta.take_state();
+        let mut partial_results = vec![];
+        let result = loop {
+            let mut alphabeta = create(10);
+            alphabeta.set_state(state);
+            let result = alphabeta.search(&fixture_board(), 9);
+            state = alphabeta.take_state();
+            if !alphabeta.is_aborted() {
+                break result.unwrap();
+            }
+            partial_results.extend(result);
+        };
+        assert!(!partial_results.is_empty());
+        assert!(partial_results
+            .iter()
+            .all(|partial| partial.bound == Bound::Lower && partial.score <= 38.0));
+        assert_eq!(result.put_position(), 1 << 6);
+        assert_eq!((result.score, result.bound), (38.0, Bound::Exact));
+    }
+
+    #[test]
+    fn order_moves_with_principal_variation() {
+        let mut alphabeta = AlphaBeta::create(usize::MAX, |board: &Board, player: &Player| {
+            board.score_numdisk(player)
+        });
+        let board = fixture_board();
+        let pv_move = Square::from_coord("b7").unwrap().bitboard();
+        let tt_move = Square::from_coord("a2").unwrap().bitboard();
+        let mut state = SearchState::default();
+        state.set_principal_variation(vec![Some(pv_move)]);
+        alphabeta.set_state(state);
+
+        let mut legal_moves = board.get_all_legal_moves(&Player::First);
+        assert!(legal_moves.contains(&pv_move) && legal_moves.contains(&tt_move));
+        alphabeta.order_moves(
+            &board,
+            &Player::First,
+            None,
+            alphabeta.pv_move(Some(0)),
+            &mut legal_moves,
+        );
+        assert_eq!(legal_moves[0], pv_move);
+        // the move of the transposition table is still searched first
+        alphabeta.order_moves(
+            &board,
+            &Player::First,
+            Some(tt_move),
+            alphabeta.pv_move(Some(0)),
+            &mut legal_moves,
+        );
+        assert_eq!(legal_moves[..2], [tt_move, pv_move]);
+
+        // the principal variation is followed only along its own path
+        assert_eq!(alphabeta.child_pv_ply(Some(0), Some(pv_move)), Some(1));
+        assert_eq!(alphabeta.child_pv_ply(Some(0), Some(tt_move)), None);
+        assert_eq!(alphabeta.child_pv_ply(None, Some(pv_move)), None);
+        assert_eq!(alphabeta.pv_move(None), None);
+    }
+
+    #[test]
+    fn search_with_principal_variation() {
+        let create = || {
+            let mut alphabeta = AlphaBeta::create(usize::MAX, |_: &Board, _: &Player| 0.0);
+            alphabeta.disable_logging();
+            alphabeta
+        };
+        let board = crate::board::new_board();
+        let legal_moves = board.get_all_legal_moves(&Player::First);
+        let result = create().search(&board, 3).unwrap();
+        assert_eq!(result.put_position(), legal_moves[0]);
+
+        // of the moves with the same score, the one of the last principal variation is kept
+        let pv_move = *legal_moves.last().unwrap();
+        let mut state = SearchState::default();
+        state.set_principal_variation(vec![Some(pv_move)]);
+        let mut alphabeta = create();
+        alphabeta.set_state(state);
+        let result = alphabeta.search(&board, 3).unwrap();
+        assert_eq!(result.put_position(), pv_move);
+        assert_eq!(
+            result.principal_variation[0],
+            Square::from_bitboard(pv_move)
+        );
+    }
 }
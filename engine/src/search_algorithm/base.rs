@@ -1,17 +1,58 @@
+use std::fmt;
+use std::ops::Neg;
+
 use crate::board::bitboard::put_position_to_coord;
 use crate::board::Board;
 use crate::board::Player;
 use crate::console_log;
 
+/// Score type of the search: `f32`, or `i32` for quantized evaluation functions so that score
+/// comparisons are exact.
+pub trait Score:
+    Copy + PartialOrd + Neg<Output = Self> + fmt::Debug + fmt::Display + 'static
+{
+    /// Upper bound of the search window. `-Score::MAX` is the lower bound.
+    const MAX: Self;
+
+    /// Score of a disc difference, used for the bounds computed from stable discs.
+    fn from_disc_difference(disc_difference: i32) -> Self;
+
+    fn as_f32(self) -> f32;
+}
+
+impl Score for f32 {
+    const MAX: f32 = f32::MAX;
+
+    fn from_disc_difference(disc_difference: i32) -> f32 {
+        disc_difference as f32
+    }
+
+    fn as_f32(self) -> f32 {
+        self
+    }
+}
+
+impl Score for i32 {
+    const MAX: i32 = i32::MAX;
+
+    fn from_disc_difference(disc_difference: i32) -> i32 {
+        disc_difference
+    }
+
+    fn as_f32(self) -> f32 {
+        self as f32
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
-pub struct GameTreeLeaf {
+pub struct GameTreeLeaf<S: Score = f32> {
     player: Player,
-    score: f32,
+    score: S,
     moves: Vec<Option<u64>>,
 }
 
-impl GameTreeLeaf {
-    pub fn create(player: Player, score: f32, moves: Vec<Option<u64>>) -> GameTreeLeaf {
+impl<S: Score> GameTreeLeaf<S> {
+    pub fn create(player: Player, score: S, moves: Vec<Option<u64>>) -> GameTreeLeaf<S> {
         GameTreeLeaf {
             player,
             score,
@@ -19,7 +60,7 @@ impl GameTreeLeaf {
         }
     }
 
-    pub fn score(&self) -> f32 {
+    pub fn score(&self) -> S {
         self.score
     }
 
@@ -28,15 +69,14 @@ impl GameTreeLeaf {
     }
 }
 
-pub trait SearchAlgorithm {
+pub trait SearchAlgorithm<S: Score = f32> {
     fn n_evaluated_leaves(&self) -> usize;
 
-    fn best_leaves(&self) -> Vec<GameTreeLeaf>;
+    fn best_leaves(&self) -> Vec<GameTreeLeaf<S>>;
 
-    #[allow(dead_code)]
     fn increment_n_evaluated_leaves(&mut self);
 
-    fn evaluate_board(&self, board: &Board, player: &Player) -> f32;
+    fn evaluate_board(&self, board: &Board, player: &Player) -> S;
 
     #[allow(dead_code)]
     fn evaluate_leaf(
@@ -44,7 +84,7 @@ pub trait SearchAlgorithm {
         player: &Player,
         board: &Board,
         put_positions: Vec<Option<u64>>,
-    ) -> GameTreeLeaf {
+    ) -> GameTreeLeaf<S> {
         self.increment_n_evaluated_leaves();
         let leaf_score = self.evaluate_board(board, player);
         GameTreeLeaf::create(player.clone(), leaf_score, put_positions)
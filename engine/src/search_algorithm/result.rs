@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+use crate::board::Square;
+use crate::search_algorithm::base::Score;
+use crate::search_algorithm::state::Bound;
+
+/// Where the move of a `SearchResult` comes from
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSource {
+    Book,
+    /// Depth-limited search with an evaluation function
+    Search,
+    /// Search to the end of the game with the disc difference
+    ExactSolver,
+    /// Search to the end of the game with win, draw or loss
+    WldSolver,
+    /// Random move, e.g. of a baseline strategy or of the exploration in self-play
+    Random,
+}
+
+/// Move chosen by a strategy with how it was found
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchResult<S: Score = f32> {
+    /// `None` means pass
+    pub best_move: Option<Square>,
+    /// Evaluation from the viewpoint of the player to move
+    pub score: S,
+    pub bound: Bound,
+    /// Expected moves from `best_move`, `None` means pass
+    pub principal_variation: Vec<Option<Square>>,
+    /// Depth of the last completed search, 0 when nothing is searched
+    pub depth: u64,
+    pub n_nodes: u64,
+    pub elapsed_ms: f64,
+    pub source: ResultSource,
+}
+
+impl<S: Score> SearchResult<S> {
+    /// Result without search statistics, e.g. a book move
+    pub fn create(best_move: Option<Square>, score: S, source: ResultSource) -> SearchResult<S> {
+        SearchResult {
+            best_move,
+            score,
+            bound: Bound::Exact,
+            principal_variation: vec![best_move],
+            depth: 0,
+            n_nodes: 0,
+            elapsed_ms: 0.0,
+            source,
+        }
+    }
+
+    pub fn with_source(self, source: ResultSource) -> SearchResult<S> {
+        SearchResult { source, ..self }
+    }
+
+    /// Same result with another move, e.g. a deliberate error, whose principal variation is
+    /// unknown
+    pub fn with_move(self, best_move: Square, score: S) -> SearchResult<S> {
+        if self.best_move == Some(best_move) {
+            return self;
+        }
+        SearchResult {
+            best_move: Some(best_move),
+            score,
+            principal_variation: vec![Some(best_move)],
+            ..self
+        }
+    }
+
+    /// Convert the score, e.g. an integer score of quantized weights to discs
+    pub fn map_score<T: Score>(self, f: impl Fn(S) -> T) -> SearchResult<T> {
+        SearchResult {
+            best_move: self.best_move,
+            score: f(self.score),
+            bound: self.bound,
+            principal_variation: self.principal_variation,
+            depth: self.depth,
+            n_nodes: self.n_nodes,
+            elapsed_ms: self.elapsed_ms,
+            source: self.source,
+        }
+    }
+
+    /// `best_move` as a bitboard, 0 for pass
+    pub fn put_position(&self) -> u64 {
+        self.best_move.map_or(0, |square| square.bitboard())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_algorithm::result::*;
+
+    #[test]
+    fn replace_move_and_score() {
+        let f5 = Square::from_coord("f5").unwrap();
+        let d6 = Square::from_coord("d6").unwrap();
+        let result = SearchResult {
+            principal_variation: vec![Some(f5), None, Some(d6)],
+            depth: 3,
+            ..SearchResult::create(Some(f5), 2, ResultSource::Search)
+        };
+        assert_eq!(result.clone().with_move(f5, 0), result);
+        let error = result.clone().with_move(d6, -4);
+        assert_eq!(error.principal_variation, vec![Some(d6)]);
+        assert_eq!((error.score, error.depth), (-4, 3));
+
+        let converted = result.map_score(|score| score as f32 * 0.5);
+        assert_eq!(converted.score, 1.0);
+        assert_eq!(converted.put_position(), 1 << 37);
+    }
+
+    #[test]
+    fn serialize() {
+        let result = SearchResult::create(None, 1.5, ResultSource::ExactSolver);
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"best_move":null,"score":1.5,"bound":"exact","principal_variation":[null],"depth":0,"n_nodes":0,"elapsed_ms":0.0,"source":"exact_solver"}"#
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::board::Board;
+use crate::board::Player;
+use crate::search_algorithm::base::Score;
+
+/// The shallowest entries are removed when the transposition table grows beyond this number of
+/// entries (see `SearchState::evict_shallow_entries`)
+const MAX_TRANSPOSITION_TABLE_ENTRIES: usize = 1 << 18;
+
+/// Whether a score is the minimax value or a bound of it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bound {
+    Exact,
+    /// The score is this value or more (beta cutoff)
+    Lower,
+    /// The score is this value or less (no move improved alpha)
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TranspositionEntry<S: Score> {
+    /// Remaining depth of the search which stored the entry
+    pub depth: u64,
+    pub score: S,
+    pub bound: Bound,
+    pub best_move: Option<u64>,
+}
+
+/// Search state kept across the moves of a game by a strategy: transposition table, history
+/// heuristic and the principal variation of the last search. It must be used only with one
+/// evaluation function (see `AlphaBeta::set_state`).
+#[derive(Clone, Debug)]
+pub struct SearchState<S: Score = f32> {
+    /// Keyed by the discs of both players and the index of the player to move
+    transposition_table: HashMap<(u64, u64, usize), TranspositionEntry<S>>,
+    /// Sum of the squared remaining depths of the beta cutoffs of each move of each player
+    history: [[u32; 64]; 2],
+    principal_variation: Vec<Option<u64>>,
+}
+
+impl<S: Score> Default for SearchState<S> {
+    fn default() -> SearchState<S> {
+        SearchState {
+            transposition_table: HashMap::new(),
+            history: [[0; 64]; 2],
+            principal_variation: vec![],
+        }
+    }
+}
+
+fn player_index(player: &Player) -> usize {
+    match player {
+        Player::First => 0,
+        Player::Second => 1,
+    }
+}
+
+impl<S: Score> SearchState<S> {
+    pub fn probe(&self, board: &Board, player: &Player) -> Option<&TranspositionEntry<S>> {
+        self.transposition_table
+            .get(&(board.first(), board.second(), player_index(player)))
+    }
+
+    /// Store an entry unless a deeper one is already stored
+    pub fn store(&mut self, board: &Board, player: &Player, entry: TranspositionEntry<S>) {
+        if self.transposition_table.len() >= MAX_TRANSPOSITION_TABLE_ENTRIES {
+            self.evict_shallow_entries();
+        }
+        let key = (board.first(), board.second(), player_index(player));
+        match self.transposition_table.get(&key) {
+            Some(stored) if stored.depth > entry.depth => (),
+            _ => {
+                self.transposition_table.insert(key, entry);
+            }
+        }
+    }
+
+    /// Remove the entries of the smallest depths until at most half of the table is used. The
+    /// deep entries are kept since they are the most expensive to search again, e.g. by the next
+    /// step of a bounded search (see `Strategy::ponder`).
+    fn evict_shallow_entries(&mut self) {
+        let mut n_entries_by_depth: BTreeMap<u64, usize> = BTreeMap::new();
+        for entry in self.transposition_table.values() {
+            *n_entries_by_depth.entry(entry.depth).or_default() += 1;
+        }
+        let mut n_entries = self.transposition_table.len();
+        let mut max_evicted_depth = None;
+        for (depth, n_entries_of_depth) in n_entries_by_depth {
+            if n_entries <= MAX_TRANSPOSITION_TABLE_ENTRIES / 2 {
+                break;
+            }
+            n_entries -= n_entries_of_depth;
+            max_evicted_depth = Some(depth);
+        }
+        if let Some(max_evicted_depth) = max_evicted_depth {
+            self.transposition_table
+                .retain(|_, entry| entry.depth > max_evicted_depth);
+        }
+    }
+
+    pub fn n_entries(&self) -> usize {
+        self.transposition_table.len()
+    }
+
+    pub fn add_cutoff(&mut self, player: &Player, put_position: u64, remaining_depth: u64) {
+        let counter =
+            &mut self.history[player_index(player)][put_position.trailing_zeros() as usize];
+        *counter = counter.saturating_add((remaining_depth * remaining_depth) as u32);
+    }
+
+    pub fn history_score(&self, player: &Player, put_position: u64) -> u32 {
+        self.history[player_index(player)][put_position.trailing_zeros() as usize]
+    }
+
+    /// Called at the beginning of each search, so that recent cutoffs weigh more
+    pub fn age_history(&mut self) {
+        for counters in self.history.iter_mut() {
+            for counter in counters.iter_mut() {
+                *counter /= 2;
+            }
+        }
+    }
+
+    /// Expected moves from the root of the last search, which was from the viewpoint of the
+    /// first player (black)
+    pub fn principal_variation(&self) -> &[Option<u64>] {
+        &self.principal_variation
+    }
+
+    pub fn set_principal_variation(&mut self, principal_variation: Vec<Option<u64>>) {
+        self.principal_variation = principal_variation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_algorithm::state::*;
+
+    #[test]
+    fn keep_deeper_entries() {
+        let board = crate::board::new_board();
+        let mut state: SearchState = SearchState::default();
+        let entry = |depth: u64| TranspositionEntry {
+            depth,
+            score: depth as f32,
+            bound: Bound::Exact,
+            best_move: None,
+        };
+        state.store(&board, &Player::First, entry(3));
+        state.store(&board, &Player::First, entry(1));
+        assert_eq!(state.probe(&board, &Player::First), Some(&entry(3)));
+        assert_eq!(state.probe(&board, &Player::Second), None);
+        state.store(&board, &Player::First, entry(5));
+        assert_eq!(state.probe(&board, &Player::First).unwrap().depth, 5);
+        assert_eq!(state.n_entries(), 1);
+    }
+
+    #[test]
+    fn evict_shallow_entries() {
+        let mut state: SearchState = SearchState::default();
+        let entry = |depth: u64| TranspositionEntry {
+            depth,
+            score: 0.0,
+            bound: Bound::Exact,
+            best_move: None,
+        };
+        // a quarter of the entries are deep
+        for i in 0..MAX_TRANSPOSITION_TABLE_ENTRIES as u64 {
+            let board = Board::create(i, 0);
+            state.store(&board, &Player::First, entry(1 + (i % 4) / 3 * 9));
+        }
+        assert_eq!(state.n_entries(), MAX_TRANSPOSITION_TABLE_ENTRIES);
+        state.store(&Board::create(0, 1), &Player::First, entry(1));
+        assert_eq!(state.n_entries(), MAX_TRANSPOSITION_TABLE_ENTRIES / 4 + 1);
+        assert_eq!(
+            state.probe(&Board::create(3, 0), &Player::First),
+            Some(&entry(10))
+        );
+        assert_eq!(state.probe(&Board::create(2, 0), &Player::First), None);
+    }
+
+    #[test]
+    fn history() {
+        let mut state: SearchState = SearchState::default();
+        state.add_cutoff(&Player::First, 1 << 19, 3);
+        assert_eq!(state.history_score(&Player::First, 1 << 19), 9);
+        assert_eq!(state.history_score(&Player::Second, 1 << 19), 0);
+        state.age_history();
+        assert_eq!(state.history_score(&Player::First, 1 << 19), 4);
+    }
+}
@@ -1,31 +1,258 @@
+pub mod baseline;
+pub mod config;
+pub mod error;
+pub mod level;
+pub mod softmax;
+
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
+#[cfg(not(feature = "quantized-weights"))]
 use crate::board::bitboard;
+use crate::board::count_bits;
 use crate::board::Board;
 use crate::board::Player;
+use crate::board::Square;
+use crate::heuristic;
+use crate::heuristic::HeuristicEvaluator;
+use crate::pattern_weights::PatternWeights;
+use crate::pattern_weights::QuantizedPatternWeights;
+use crate::rng::Rng;
 use crate::search_algorithm::AlphaBeta;
+use crate::search_algorithm::ResultSource;
+use crate::search_algorithm::Score;
+use crate::search_algorithm::SearchAlgorithm;
+use crate::search_algorithm::SearchResult;
+use crate::search_algorithm::SearchState;
+use crate::strategy::error::StrategyError;
+use crate::win_probability;
 
 #[wasm_bindgen]
 #[derive(Debug)]
 pub enum StrategyType {
     NumdiskLookahead,
     PatternLookahead1,
+    HeuristicLookahead,
+    /// Baselines for testing (see `baseline`)
+    Random,
+    Greedy,
+    Mobility,
+}
+
+impl StrategyType {
+    /// `seed` is used by the random strategies
+    pub fn create_strategy(&self, seed: u64) -> Box<dyn Strategy> {
+        match self {
+            StrategyType::NumdiskLookahead => Box::new(NumdiskLookaheadStrategy::default()),
+            StrategyType::PatternLookahead1 => Box::new(PatternLookahead1Strategy {}),
+            StrategyType::HeuristicLookahead => Box::new(HeuristicLookaheadStrategy::default()),
+            StrategyType::Random => Box::new(baseline::RandomStrategy::create(Rng::create(seed))),
+            StrategyType::Greedy => Box::new(baseline::GreedyStrategy {}),
+            StrategyType::Mobility => Box::new(baseline::MobilityStrategy {}),
+        }
+    }
 }
 
 pub fn new_strategy() -> NumdiskLookaheadStrategy {
-    NumdiskLookaheadStrategy {}
+    NumdiskLookaheadStrategy::default()
 }
 
 pub trait Strategy {
+    /// Move of `player`, who has at least one legal move, on the `i_step`-th move of the game
     fn get_next_move(
         &mut self,
         board: &Board,
         player: &Player,
         i_step: usize,
-    ) -> Result<(Option<u64>, f32), String>;
+    ) -> Result<SearchResult, StrategyError>;
+
+    /// Scores of all the legal moves of `player` in descending order (Multi-PV). By default, each
+    /// child position is searched by `get_next_move`.
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        let mut scores = Vec::new();
+        for put_position in board.get_all_legal_moves(player) {
+            let mut child = board.clone();
+            child.put_and_reverse(player, put_position);
+            let score = if child.legal_moves(&player.opponent()) != 0 {
+                -self
+                    .get_next_move(&child, &player.opponent(), i_step + 1)?
+                    .score
+            } else if child.legal_moves(player) != 0 {
+                self.get_next_move(&child, player, i_step + 1)?.score
+            } else {
+                child.score_numdisk(player)
+            };
+            scores.push((put_position, score));
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scores)
+    }
+
+    /// Forget the state kept across the moves of a game, e.g. the transposition table. Called
+    /// before the first move of each game.
+    fn new_game(&mut self) {}
+
+    /// Search a position which may arise after the reply of the opponent (see `Ponderer`), in
+    /// units of about `max_n_leaves` evaluated leaves so that the caller is not blocked, e.g. the
+    /// main thread in wasm. Only the state of the strategy is filled, e.g. its transposition
+    /// table, so that `get_next_move` finds its move faster, and no random number is drawn so
+    /// that the moves do not depend on pondering. Returns whether the search is finished, and
+    /// otherwise the next call continues it. Strategies without search state do not support it.
+    fn ponder(
+        &mut self,
+        _board: &Board,
+        _player: &Player,
+        _i_step: usize,
+        _max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        Err(StrategyError::PonderingNotSupported)
+    }
+
+    /// Reply of the opponent expected by the last search, i.e. the second move of its principal
+    /// variation
+    fn expected_reply(&self) -> Option<Square> {
+        None
+    }
+
+    /// Scale of the logistic function from the scores to win probabilities (see
+    /// `win_probability::win_probability`), which depends on the units of the evaluator
+    fn win_probability_scale(&self) -> f32 {
+        win_probability::DEFAULT_SCALE
+    }
 }
 
-pub struct NumdiskLookaheadStrategy {}
+impl<T: Strategy + ?Sized> Strategy for Box<T> {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        (**self).get_next_move(board, player, i_step)
+    }
+
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        (**self).root_move_scores(board, player, i_step)
+    }
+
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        (**self).ponder(board, player, i_step, max_n_leaves)
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        (**self).expected_reply()
+    }
+
+    fn win_probability_scale(&self) -> f32 {
+        (**self).win_probability_scale()
+    }
+}
+
+/// Second move of the principal variation of the last search with `state`
+fn expected_reply_of<S: Score>(state: &SearchState<S>) -> Option<Square> {
+    state
+        .principal_variation()
+        .get(1)
+        .copied()
+        .flatten()
+        .and_then(Square::from_bitboard)
+}
+
+/// Search of `board` for `player` by `alphabeta`, continuing from `state` which is updated
+fn search_with_state<S: Score>(
+    alphabeta: &mut AlphaBeta<S>,
+    state: &mut SearchState<S>,
+    board: &Board,
+    player: &Player,
+    depth: u64,
+) -> Option<SearchResult<S>> {
+    let root_board = match player {
+        Player::First => board.clone(),
+        Player::Second => Board::reverse(board),
+    };
+    alphabeta.set_state(std::mem::take(state));
+    let result = alphabeta.search(&root_board, depth);
+    *state = alphabeta.take_state();
+    result
+}
+
+/// Result of a search of a strategy, where a pass is not expected. The search reaching the end
+/// of the game with `exact` evaluation (the disc difference) is from the solver.
+fn search_result_of(
+    root_board: &Board,
+    result: Option<SearchResult>,
+    exact: bool,
+) -> Result<SearchResult, StrategyError> {
+    match result {
+        Some(result) if result.best_move.is_some() => {
+            let n_empties = count_bits(!(root_board.first() | root_board.second()));
+            Ok(if exact && result.depth >= n_empties {
+                result.with_source(ResultSource::ExactSolver)
+            } else {
+                result
+            })
+        }
+        _ => Err(StrategyError::EmptySearchResult),
+    }
+}
+
+/// Scores of the root moves from the best leaves of a search, in descending order. The scores
+/// are multiplied by `scale`, e.g. the scale of quantized weights.
+fn best_leaves_root_scores<S: Score>(alphabeta: &AlphaBeta<S>, scale: f32) -> Vec<(u64, f32)> {
+    alphabeta
+        .best_leaves()
+        .iter()
+        .filter_map(|leaf| {
+            leaf.moves()[0].map(|put_position| (put_position, leaf.score().as_f32() * scale))
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct NumdiskLookaheadStrategy {
+    state: SearchState,
+}
+
+impl NumdiskLookaheadStrategy {
+    /// Search aborted after `max_n_leaves` evaluated leaves (see `AlphaBeta::search`)
+    fn search(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> (AlphaBeta, Option<SearchResult>) {
+        let mut alphabeta =
+            AlphaBeta::create(max_n_leaves, |board: &Board, player: &Player| -> f32 {
+                board.score_numdisk(player)
+            });
+        alphabeta.enable_stability_cutoff();
+        alphabeta.enable_parity_ordering();
+        let depth = config::StrategyConfig::default().depth(i_step);
+        let result = search_with_state(&mut alphabeta, &mut self.state, board, player, depth);
+        (alphabeta, result)
+    }
+}
 
 impl Strategy for NumdiskLookaheadStrategy {
     fn get_next_move(
@@ -33,35 +260,41 @@ impl Strategy for NumdiskLookaheadStrategy {
         board: &Board,
         player: &Player,
         i_step: usize,
-    ) -> Result<(Option<u64>, f32), String> {
-        let mut alphabeta = AlphaBeta::create(500000, |board: &Board, player: &Player| -> f32 {
-            board.score_numdisk(player)
-        });
-        let root_board = match player {
-            Player::First => board.clone(),
-            Player::Second => Board::reverse(board),
-        };
-        let depth = match i_step {
-            45..=61 => 13,
-            41..=44 => 9,
-            _ => 7,
-        };
-        match alphabeta.search(&root_board, depth) {
-            Some((Some(best_move), score)) => Ok((Some(best_move), score)),
-            _ => Err(String::from("Result of alpha-beta pruning search is empty")),
-        }
+    ) -> Result<SearchResult, StrategyError> {
+        let (_, result) = self.search(board, player, i_step, 500000);
+        search_result_of(board, result, true)
+    }
+
+    fn new_game(&mut self) {
+        self.state = SearchState::default();
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        let (alphabeta, _) = self.search(board, player, i_step, max_n_leaves);
+        Ok(!alphabeta.is_aborted())
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        expected_reply_of(&self.state)
     }
 }
 
 pub struct PatternLookahead1Strategy {}
 
 impl Strategy for PatternLookahead1Strategy {
+    #[cfg(not(feature = "quantized-weights"))]
     fn get_next_move(
         &mut self,
         board: &Board,
         player: &Player,
         _i_step: usize,
-    ) -> Result<(Option<u64>, f32), String> {
+    ) -> Result<SearchResult, StrategyError> {
         let mut alphabeta =
             AlphaBeta::create(1000000000, |board: &Board, player: &Player| -> f32 {
                 let pattern_instance_indices =
@@ -72,9 +305,345 @@ impl Strategy for PatternLookahead1Strategy {
             Player::First => board.clone(),
             Player::Second => Board::reverse(board),
         };
-        match alphabeta.search(&root_board, 0) {
-            Some((Some(best_move), score)) => Ok((Some(best_move), score)),
-            _ => Err(String::from("Result of alpha-beta pruning search is empty")),
+        search_result_of(&root_board, alphabeta.search(&root_board, 0), false)
+    }
+
+    /// Search with integer scores, and convert the best score with the scale of the weights.
+    #[cfg(feature = "quantized-weights")]
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let weights = QuantizedPatternWeights::default();
+        let scale = weights.scale();
+        let mut alphabeta =
+            AlphaBeta::create(1000000000, move |board: &Board, player: &Player| -> i32 {
+                weights.score(board, player)
+            });
+        let root_board = match player {
+            Player::First => board.clone(),
+            Player::Second => Board::reverse(board),
+        };
+        let result = alphabeta
+            .search(&root_board, 0)
+            .map(|result| result.map_score(|score| score as f32 * scale));
+        search_result_of(&root_board, result, false)
+    }
+}
+
+pub struct HeuristicLookaheadStrategy {
+    evaluator: HeuristicEvaluator,
+    /// Only the depth schedule is used, since the evaluator is given separately
+    config: config::StrategyConfig,
+    state: SearchState,
+}
+
+impl Default for HeuristicLookaheadStrategy {
+    fn default() -> HeuristicLookaheadStrategy {
+        HeuristicLookaheadStrategy::create(
+            HeuristicEvaluator::default(),
+            config::StrategyConfig::heuristic(),
+        )
+    }
+}
+
+impl HeuristicLookaheadStrategy {
+    pub fn create(
+        evaluator: HeuristicEvaluator,
+        config: config::StrategyConfig,
+    ) -> HeuristicLookaheadStrategy {
+        HeuristicLookaheadStrategy {
+            evaluator,
+            config,
+            state: SearchState::default(),
+        }
+    }
+}
+
+impl HeuristicLookaheadStrategy {
+    /// Search aborted after `max_n_leaves` evaluated leaves (see `AlphaBeta::search`)
+    fn search(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> (AlphaBeta, Option<SearchResult>) {
+        let evaluator = self.evaluator.clone();
+        let mut alphabeta =
+            AlphaBeta::create(max_n_leaves, move |board: &Board, player: &Player| -> f32 {
+                evaluator.evaluate(board, player)
+            });
+        alphabeta.enable_parity_ordering();
+        let depth = self.config.depth(i_step);
+        let result = search_with_state(&mut alphabeta, &mut self.state, board, player, depth);
+        (alphabeta, result)
+    }
+}
+
+impl Strategy for HeuristicLookaheadStrategy {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let (_, result) = self.search(board, player, i_step, 500000);
+        search_result_of(board, result, false)
+    }
+
+    fn new_game(&mut self) {
+        self.state = SearchState::default();
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        let (alphabeta, _) = self.search(board, player, i_step, max_n_leaves);
+        Ok(!alphabeta.is_aborted())
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        expected_reply_of(&self.state)
+    }
+
+    fn win_probability_scale(&self) -> f32 {
+        heuristic::WIN_PROBABILITY_SCALE
+    }
+}
+
+type EvaluateBoardFunc<S> = Rc<dyn Fn(&Board, &Player) -> S>;
+
+/// Fixed-depth alpha-beta search with an arbitrary evaluation function,
+/// e.g. `NeuralNetwork::evaluate` or `PatternWeights::score` loaded from a file.
+pub struct LookaheadStrategy<S: Score = f32> {
+    depth: u64,
+    max_n_leaves: usize,
+    evaluate_board_func: EvaluateBoardFunc<S>,
+    /// Multiplied to the scores of the search, e.g. the scale of quantized weights
+    scale: f32,
+    logging: bool,
+    state: SearchState<S>,
+}
+
+impl LookaheadStrategy {
+    pub fn create(
+        depth: u64,
+        max_n_leaves: usize,
+        evaluate_board_func: impl Fn(&Board, &Player) -> f32 + 'static,
+    ) -> LookaheadStrategy {
+        LookaheadStrategy::create_with_scale(depth, max_n_leaves, evaluate_board_func, 1.0)
+    }
+}
+
+impl LookaheadStrategy<i32> {
+    /// Search with integer scores of `weights`, so that score comparisons are exact
+    pub fn create_quantized(
+        depth: u64,
+        max_n_leaves: usize,
+        weights: QuantizedPatternWeights,
+    ) -> LookaheadStrategy<i32> {
+        let scale = weights.scale();
+        LookaheadStrategy::create_with_scale(
+            depth,
+            max_n_leaves,
+            move |board: &Board, player: &Player| -> i32 { weights.score(board, player) },
+            scale,
+        )
+    }
+}
+
+impl<S: Score> LookaheadStrategy<S> {
+    fn create_with_scale(
+        depth: u64,
+        max_n_leaves: usize,
+        evaluate_board_func: impl Fn(&Board, &Player) -> S + 'static,
+        scale: f32,
+    ) -> LookaheadStrategy<S> {
+        LookaheadStrategy {
+            depth,
+            max_n_leaves,
+            evaluate_board_func: Rc::new(evaluate_board_func),
+            scale,
+            logging: true,
+            state: SearchState::default(),
+        }
+    }
+
+    pub fn disable_logging(&mut self) {
+        self.logging = false;
+    }
+
+    /// Search aborted after `max_n_leaves` evaluated leaves (see `AlphaBeta::search`)
+    fn search(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        max_n_leaves: usize,
+    ) -> (AlphaBeta<S>, Option<SearchResult>) {
+        let evaluate = self.evaluate_board_func.clone();
+        let mut alphabeta =
+            AlphaBeta::create(max_n_leaves, move |board: &Board, player: &Player| -> S {
+                evaluate(board, player)
+            });
+        if !self.logging {
+            alphabeta.disable_logging();
+        }
+        let scale = self.scale;
+        let result = search_with_state(&mut alphabeta, &mut self.state, board, player, self.depth)
+            .map(|result| result.map_score(|score| score.as_f32() * scale));
+        (alphabeta, result)
+    }
+}
+
+/// Score of the searches with pattern weights, integers with the `quantized-weights` feature
+#[cfg(not(feature = "quantized-weights"))]
+pub type PatternScore = f32;
+#[cfg(feature = "quantized-weights")]
+pub type PatternScore = i32;
+
+/// `LookaheadStrategy` evaluating with `weights`, which are quantized with the
+/// `quantized-weights` feature
+pub fn pattern_lookahead(
+    depth: u64,
+    max_n_leaves: usize,
+    weights: PatternWeights,
+) -> LookaheadStrategy<PatternScore> {
+    #[cfg(not(feature = "quantized-weights"))]
+    {
+        LookaheadStrategy::create(
+            depth,
+            max_n_leaves,
+            move |board: &Board, player: &Player| -> f32 { weights.score(board, player) },
+        )
+    }
+    #[cfg(feature = "quantized-weights")]
+    {
+        LookaheadStrategy::create_quantized(
+            depth,
+            max_n_leaves,
+            QuantizedPatternWeights::quantize(&weights),
+        )
+    }
+}
+
+impl<S: Score> Strategy for LookaheadStrategy<S> {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        search_result_of(
+            board,
+            self.search(board, player, self.max_n_leaves).1,
+            false,
+        )
+    }
+
+    /// Every root move is searched with the full window, so its score is exact
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        Ok(best_leaves_root_scores(
+            &self.search(board, player, self.max_n_leaves).0,
+            self.scale,
+        ))
+    }
+
+    fn new_game(&mut self) {
+        self.state = SearchState::default();
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        let (alphabeta, _) = self.search(board, player, max_n_leaves);
+        Ok(!alphabeta.is_aborted())
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        expected_reply_of(&self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::config::StrategyConfig;
+    use crate::strategy::*;
+
+    #[test]
+    fn heuristic_lookahead_depth_schedule() {
+        let config = StrategyConfig::heuristic();
+        assert_eq!(
+            [config.depth(0), config.depth(41), config.depth(45)],
+            [5, 7, 13]
+        );
+        let mut strategy = HeuristicLookaheadStrategy::create(
+            HeuristicEvaluator::default(),
+            StrategyConfig {
+                depth_schedule: vec![(0, 1), (2, 3)],
+                ..config
+            },
+        );
+        let mut board = crate::board::new_board();
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert_eq!(result.depth, 1);
+        board.put_and_reverse(&Player::First, result.put_position());
+        board.put_and_reverse(
+            &Player::Second,
+            board.get_all_legal_moves(&Player::Second)[0],
+        );
+        let result = strategy.get_next_move(&board, &Player::First, 2).unwrap();
+        assert_eq!(result.depth, 3);
+    }
+
+    #[test]
+    fn quantized_lookahead_scores_in_discs() {
+        let weights = PatternWeights::default();
+        let quantized = QuantizedPatternWeights::quantize(&weights);
+        let scale = quantized.scale();
+        let mut float_strategy = LookaheadStrategy::create(
+            2,
+            usize::MAX,
+            move |board: &Board, player: &Player| -> f32 { weights.score(board, player) },
+        );
+        float_strategy.disable_logging();
+        let mut quantized_strategy = LookaheadStrategy::create_quantized(2, usize::MAX, quantized);
+        quantized_strategy.disable_logging();
+
+        let mut board = crate::board::new_board();
+        board.put_and_reverse(&Player::First, 1 << 37); // f5
+        let float_scores = float_strategy
+            .root_move_scores(&board, &Player::Second, 1)
+            .unwrap();
+        let quantized_scores = quantized_strategy
+            .root_move_scores(&board, &Player::Second, 1)
+            .unwrap();
+        assert_eq!(float_scores.len(), quantized_scores.len());
+        for (put_position, score) in &quantized_scores {
+            // integer scores in units of the scale
+            assert_eq!((score / scale).round() * scale, *score);
+            let (_, float_score) = float_scores.iter().find(|s| s.0 == *put_position).unwrap();
+            // the rounding error of each of the dozens of pattern instances is at most scale / 2
+            assert!((score - float_score).abs() < 100.0 * scale);
         }
+        let result = quantized_strategy
+            .get_next_move(&board, &Player::Second, 1)
+            .unwrap();
+        assert_eq!(result.score, quantized_scores[0].1);
     }
 }
@@ -0,0 +1,199 @@
+use crate::board::count_bits;
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::rng::Rng;
+use crate::search_algorithm::ResultSource;
+use crate::search_algorithm::SearchResult;
+use crate::strategy::error::StrategyError;
+use crate::strategy::Strategy;
+
+/// Legal move with the best score of `score_move`, the first one in the bit order on ties
+fn best_move_by(
+    board: &Board,
+    player: &Player,
+    score_move: impl Fn(&Board, u64) -> f32,
+) -> Result<SearchResult, StrategyError> {
+    board
+        .get_all_legal_moves(player)
+        .into_iter()
+        .map(|put_position| (put_position, score_move(board, put_position)))
+        .reduce(|a, b| if b.1 > a.1 { b } else { a })
+        .map(|(put_position, score)| SearchResult {
+            depth: 1,
+            ..SearchResult::create(
+                Square::from_bitboard(put_position),
+                score,
+                ResultSource::Search,
+            )
+        })
+        .ok_or(StrategyError::NoLegalMoves)
+}
+
+/// Uniformly random legal move. The score is always 0.
+pub struct RandomStrategy {
+    rng: Rng,
+}
+
+impl RandomStrategy {
+    pub fn create(rng: Rng) -> RandomStrategy {
+        RandomStrategy { rng }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let moves = board.get_all_legal_moves(player);
+        if moves.is_empty() {
+            return Err(StrategyError::NoLegalMoves);
+        }
+        let put_position = moves[self.rng.next_usize(moves.len())];
+        Ok(SearchResult::create(
+            Square::from_bitboard(put_position),
+            0.0,
+            ResultSource::Random,
+        ))
+    }
+}
+
+/// Move flipping the most discs. The score is the number of flipped discs.
+pub struct GreedyStrategy {}
+
+impl Strategy for GreedyStrategy {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let own = |board: &Board| match player {
+            Player::First => count_bits(board.first()),
+            Player::Second => count_bits(board.second()),
+        };
+        best_move_by(board, player, |board, put_position| {
+            let mut child = board.clone();
+            child.put_and_reverse(player, put_position);
+            // the own discs increase by the flipped discs and the placed one
+            (own(&child) - own(board) - 1) as f32
+        })
+    }
+}
+
+/// One-ply search minimizing the number of legal moves of the opponent. The score is the
+/// negated mobility of the opponent.
+pub struct MobilityStrategy {}
+
+impl Strategy for MobilityStrategy {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        _i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        best_move_by(board, player, |board, put_position| {
+            let mut child = board.clone();
+            child.put_and_reverse(player, put_position);
+            -(count_bits(child.legal_moves(&player.opponent())) as f32)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena;
+    use crate::strategy::baseline::*;
+
+    #[test]
+    fn random_is_seedable() {
+        let board = crate::board::new_board();
+        let play = |seed: u64| -> Vec<u64> {
+            let mut strategy = RandomStrategy::create(Rng::create(seed));
+            (0..20)
+                .map(|_| {
+                    strategy
+                        .get_next_move(&board, &Player::First, 0)
+                        .unwrap()
+                        .put_position()
+                })
+                .collect()
+        };
+        assert_eq!(play(1), play(1));
+        assert!(play(1).iter().any(|&m| m != play(1)[0]));
+        assert!(play(1)
+            .iter()
+            .all(|&m| board.legal_moves(&Player::First) & m != 0));
+    }
+
+    #[test]
+    fn greedy_flips_most() {
+        let board = Board::create_from_str(
+            "
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - o x x x -
+            - - - x o - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            ",
+        );
+        let mut strategy = GreedyStrategy {};
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert_eq!(result.put_position(), 1 << 31); // h4
+        assert_eq!(result.score, 3.0);
+    }
+
+    #[test]
+    fn mobility_minimizes_opponent_moves() {
+        let board = crate::board::new_board();
+        let result = MobilityStrategy {}
+            .get_next_move(&board, &Player::First, 0)
+            .unwrap();
+        let mut child = board.clone();
+        child.put_and_reverse(&Player::First, result.put_position());
+        assert_eq!(
+            result.score,
+            -(count_bits(child.legal_moves(&Player::Second)) as f32)
+        );
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let board = Board::create_from_str(
+            "
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o o
+            o o o o o o o -
+            ",
+        );
+        assert_eq!(
+            GreedyStrategy {}.get_next_move(&board, &Player::Second, 60),
+            Err(StrategyError::NoLegalMoves)
+        );
+        assert!(RandomStrategy::create(Rng::create(0))
+            .get_next_move(&board, &Player::Second, 60)
+            .is_err());
+    }
+
+    #[test]
+    fn baselines_play_full_games() {
+        let mut random = RandomStrategy::create(Rng::create(0));
+        let mut greedy = GreedyStrategy {};
+        let mut mobility = MobilityStrategy {};
+        let record = arena::play_game(&mut random, &mut greedy, &[]).unwrap();
+        assert!(record.board.legal_moves(&Player::First) == 0);
+        let record = arena::play_game(&mut mobility, &mut random, &[]).unwrap();
+        assert!(record.board.legal_moves(&Player::Second) == 0);
+    }
+}
@@ -0,0 +1,843 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::board::bitboard;
+use crate::board::count_bits;
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::book::Book;
+use crate::book::BookStrategy;
+use crate::heuristic;
+use crate::heuristic::HeuristicEvaluator;
+use crate::pattern_weights::PatternWeights;
+#[cfg(feature = "quantized-weights")]
+use crate::pattern_weights::QuantizedPatternWeights;
+use crate::rng::Rng;
+use crate::search_algorithm::AlphaBeta;
+use crate::search_algorithm::ResultSource;
+use crate::search_algorithm::Score;
+use crate::search_algorithm::SearchAlgorithm;
+use crate::search_algorithm::SearchResult;
+use crate::search_algorithm::SearchState;
+use crate::strategy::best_leaves_root_scores;
+use crate::strategy::error::StrategyError;
+use crate::strategy::softmax;
+use crate::strategy::Strategy;
+use crate::utils;
+use crate::win_probability;
+
+type EvaluateBoardFunc = Box<dyn Fn(&Board, &Player) -> f32>;
+
+/// Search of the root moves by `ConfiguredStrategy`
+struct RootSearch {
+    /// Scores of the root moves in descending order
+    scores: Vec<(u64, f32)>,
+    /// Result of the search of the best move
+    result: Option<SearchResult>,
+    /// Whether the search is aborted by `max_n_leaves` or the time budget, and the result is of
+    /// the last finished depth or of the root moves searched before
+    aborted: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvaluatorType {
+    Numdisk,
+    Heuristic,
+    Pattern,
+}
+
+impl EvaluatorType {
+    /// `weights` replaces the built-in weights of the pattern evaluator
+    fn evaluate_board_func(&self, weights: Option<&Arc<PatternWeights>>) -> EvaluateBoardFunc {
+        if let (EvaluatorType::Pattern, Some(weights)) = (self, weights) {
+            let weights = weights.clone();
+            return Box::new(move |board: &Board, player: &Player| -> f32 {
+                weights.score(board, player)
+            });
+        }
+        match self {
+            EvaluatorType::Numdisk => {
+                Box::new(|board: &Board, player: &Player| -> f32 { board.score_numdisk(player) })
+            }
+            EvaluatorType::Heuristic => {
+                let evaluator = HeuristicEvaluator::default();
+                Box::new(move |board: &Board, player: &Player| -> f32 {
+                    evaluator.evaluate(board, player)
+                })
+            }
+            EvaluatorType::Pattern => Box::new(|board: &Board, player: &Player| -> f32 {
+                Board::calculate_pattern_score(bitboard::extract_pattern_instance_indices(
+                    board, player,
+                ))
+            }),
+        }
+    }
+
+    /// See `Strategy::win_probability_scale`. The pattern weights are fitted in discs.
+    fn win_probability_scale(&self) -> f32 {
+        match self {
+            EvaluatorType::Numdisk | EvaluatorType::Pattern => win_probability::DEFAULT_SCALE,
+            EvaluatorType::Heuristic => heuristic::WIN_PROBABILITY_SCALE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookConfig {
+    /// Book file (see `Book`), loaded by `build_strategy`. In wasm, the book is given to
+    /// `Game::load_book` instead.
+    pub path: Option<String>,
+    /// Book moves within this number of discs from the best one are chosen at random
+    pub randomness: f32,
+}
+
+/// Description of a search strategy, e.g.
+///
+/// ```json
+/// {"depth_schedule": [[0, 4], [40, 6]], "time_budget_ms": 1000, "wld_empties": 16,
+///  "exact_empties": 14, "evaluator": "pattern", "weights": "weights.txt",
+///  "book": {"path": "book.bin", "randomness": 1}}
+/// ```
+///
+/// Omitted fields take the values of `StrategyConfig::default()`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyConfig {
+    /// Midgame depth as `(from_step, depth)` sorted by step: the depth of the last pair whose
+    /// step has been reached is used
+    pub depth_schedule: Vec<(usize, u64)>,
+    /// Iterative deepening up to the scheduled depth. Deeper iterations are not started after a
+    /// quarter of this time has passed, and are aborted when it has passed, falling back to the
+    /// previous depth. The solvers are aborted after half of this time.
+    pub time_budget_ms: Option<f64>,
+    /// Win/loss/draw is solved when the number of empty squares is this value or less, and the
+    /// scores are -1, 0 or 1
+    pub wld_empties: u64,
+    /// The disc difference is solved when the number of empty squares is this value or less
+    pub exact_empties: u64,
+    pub evaluator: EvaluatorType,
+    /// Weights file of the pattern evaluator (see `PatternWeights::load`), loaded by
+    /// `build_strategy`. The built-in weights are used when omitted.
+    pub weights: Option<String>,
+    pub book: Option<BookConfig>,
+    /// Probability of playing a random move other than the best one
+    pub error_rate: f32,
+    /// Temperature of the softmax move selection (see `softmax::sample_move`), 0 for the best move
+    pub temperature: f32,
+    /// Moves losing more than this from the best move are never sampled by the softmax
+    pub max_loss: f32,
+}
+
+impl Default for StrategyConfig {
+    /// The schedule of `NumdiskLookaheadStrategy`
+    fn default() -> StrategyConfig {
+        StrategyConfig {
+            depth_schedule: vec![(0, 7), (41, 9), (45, 13)],
+            time_budget_ms: None,
+            wld_empties: 0,
+            exact_empties: 0,
+            evaluator: EvaluatorType::Numdisk,
+            weights: None,
+            book: None,
+            error_rate: 0.0,
+            temperature: 0.0,
+            max_loss: 0.0,
+        }
+    }
+}
+
+impl StrategyConfig {
+    pub fn from_json(json: &str) -> Result<StrategyConfig, String> {
+        serde_json::from_str(json).map_err(|e| format!("invalid strategy config: {}", e))
+    }
+
+    pub fn load(path: &str) -> Result<StrategyConfig, String> {
+        let json =
+            std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        StrategyConfig::from_json(&json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// The schedule of `HeuristicLookaheadStrategy`
+    pub fn heuristic() -> StrategyConfig {
+        StrategyConfig {
+            depth_schedule: vec![(0, 5), (41, 7), (45, 13)],
+            evaluator: EvaluatorType::Heuristic,
+            ..StrategyConfig::default()
+        }
+    }
+
+    pub fn depth(&self, i_step: usize) -> u64 {
+        self.depth_schedule
+            .iter()
+            .rev()
+            .find(|(from_step, _)| *from_step <= i_step)
+            .map_or(0, |(_, depth)| *depth)
+    }
+}
+
+/// Search each depth of `depths` with `state`, and stop deepening after a quarter of
+/// `time_budget_ms`. The scores are multiplied by `scale` (see `best_leaves_root_scores`), and
+/// the result has the nodes and time of all the depths. The deepening is aborted when more than
+/// `max_n_leaves` leaves are evaluated in total, and `create_alphabeta` is given the rest of them,
+/// or when the depths after the first one run out of `time_budget_ms`. The result is then of the
+/// last finished depth, or of the root moves searched before the abort when no depth is finished.
+fn iterative_deepening<S: Score>(
+    root_board: &Board,
+    depths: std::ops::RangeInclusive<u64>,
+    time_budget_ms: Option<f64>,
+    max_n_leaves: usize,
+    scale: f32,
+    create_alphabeta: impl Fn(usize) -> AlphaBeta<S>,
+    state: &mut SearchState<S>,
+) -> RootSearch {
+    let started_at = utils::now_millis();
+    let min_depth = *depths.start();
+    let mut scores = vec![];
+    let mut result: Option<SearchResult> = None;
+    let mut n_nodes = 0;
+    let mut n_leaves = 0;
+    let mut aborted = false;
+    for depth in depths {
+        if let Some(time_budget_ms) = time_budget_ms
+            && depth > min_depth
+            && utils::now_millis() - started_at > time_budget_ms / 4.0
+        {
+            break;
+        }
+        let mut alphabeta = create_alphabeta(max_n_leaves.saturating_sub(n_leaves));
+        if let Some(time_budget_ms) = time_budget_ms
+            && depth > min_depth
+        {
+            alphabeta.set_deadline(started_at + time_budget_ms);
+        }
+        alphabeta.set_state(std::mem::take(state));
+        let depth_result = alphabeta.search(root_board, depth);
+        *state = alphabeta.take_state();
+        n_leaves += alphabeta.n_evaluated_leaves();
+        aborted = alphabeta.is_aborted();
+        let depth_result = depth_result.map(|result| result.map_score(|s| s.as_f32() * scale));
+        n_nodes += depth_result.as_ref().map_or(0, |result| result.n_nodes);
+        if !aborted || result.is_none() {
+            result = depth_result;
+            scores = best_leaves_root_scores(&alphabeta, scale);
+        }
+        if aborted {
+            break;
+        }
+    }
+    let result = result.map(|result| SearchResult {
+        n_nodes,
+        elapsed_ms: utils::now_millis() - started_at,
+        ..result
+    });
+    RootSearch {
+        scores,
+        result,
+        aborted,
+    }
+}
+
+/// Strategy described by a `StrategyConfig`, except for the book (see `build_strategy`)
+pub struct ConfiguredStrategy {
+    config: StrategyConfig,
+    rng: Rng,
+    weights: Option<Arc<PatternWeights>>,
+    /// Weights of the pattern evaluator, whose scores are summed up in integers
+    #[cfg(feature = "quantized-weights")]
+    quantized_weights: Arc<QuantizedPatternWeights>,
+    /// Search states of the exact, WLD and midgame searches, which have different evaluation
+    /// functions
+    exact_state: SearchState,
+    wld_state: SearchState,
+    midgame_state: SearchState,
+    #[cfg(feature = "quantized-weights")]
+    quantized_midgame_state: SearchState<i32>,
+    /// Result of the last search, from any of the states
+    last_result: Option<SearchResult>,
+}
+
+impl ConfiguredStrategy {
+    pub fn create(config: StrategyConfig, rng: Rng) -> ConfiguredStrategy {
+        ConfiguredStrategy {
+            config,
+            rng,
+            weights: None,
+            #[cfg(feature = "quantized-weights")]
+            quantized_weights: Arc::new(QuantizedPatternWeights::default()),
+            exact_state: SearchState::default(),
+            wld_state: SearchState::default(),
+            midgame_state: SearchState::default(),
+            #[cfg(feature = "quantized-weights")]
+            quantized_midgame_state: SearchState::default(),
+            last_result: None,
+        }
+    }
+
+    /// Weights of the pattern evaluator instead of the built-in ones
+    pub fn set_weights(&mut self, weights: PatternWeights) {
+        #[cfg(feature = "quantized-weights")]
+        {
+            self.quantized_weights = Arc::new(QuantizedPatternWeights::quantize(&weights));
+        }
+        self.weights = Some(Arc::new(weights));
+    }
+
+    /// Scores of the legal moves at the root in descending order, with the result of the search
+    /// of the best move. When pondering, the search is bounded by `max_n_leaves` (see
+    /// `Strategy::ponder`) instead of the time budget. The solvers are given half of the time
+    /// budget, and the midgame search is done with the rest when they do not finish in time.
+    fn search_root_moves(
+        &mut self,
+        root_board: &Board,
+        i_step: usize,
+        max_n_leaves: Option<usize>,
+    ) -> RootSearch {
+        let started_at = utils::now_millis();
+        let n_empties = count_bits(!(root_board.first() | root_board.second()));
+        let pondering = max_n_leaves.is_some();
+        let max_n_leaves = max_n_leaves.unwrap_or(usize::MAX);
+        let time_budget_ms = self.config.time_budget_ms.filter(|_| !pondering);
+        let solver_deadline =
+            time_budget_ms.map(|time_budget_ms| started_at + time_budget_ms / 2.0);
+        if n_empties <= self.config.exact_empties {
+            let mut alphabeta =
+                AlphaBeta::create(max_n_leaves, |board: &Board, player: &Player| {
+                    board.score_numdisk(player)
+                });
+            alphabeta.disable_logging();
+            alphabeta.enable_stability_cutoff();
+            alphabeta.enable_parity_ordering();
+            if let Some(deadline_ms) = solver_deadline {
+                alphabeta.set_deadline(deadline_ms);
+            }
+            alphabeta.set_state(std::mem::take(&mut self.exact_state));
+            let result = alphabeta.search(root_board, n_empties);
+            self.exact_state = alphabeta.take_state();
+            if pondering || !alphabeta.is_aborted() {
+                return RootSearch {
+                    scores: best_leaves_root_scores(&alphabeta, 1.0),
+                    result: result.map(|result| result.with_source(ResultSource::ExactSolver)),
+                    aborted: alphabeta.is_aborted(),
+                };
+            }
+        } else if n_empties <= self.config.wld_empties {
+            // `f32::signum` is 1 or -1 for a zero, so a draw is compared explicitly
+            let mut alphabeta =
+                AlphaBeta::create(max_n_leaves, |board: &Board, player: &Player| {
+                    match board.score_numdisk(player).partial_cmp(&0.0) {
+                        Some(Ordering::Greater) => 1.0,
+                        Some(Ordering::Less) => -1.0,
+                        _ => 0.0,
+                    }
+                });
+            alphabeta.disable_logging();
+            alphabeta.enable_parity_ordering();
+            if let Some(deadline_ms) = solver_deadline {
+                alphabeta.set_deadline(deadline_ms);
+            }
+            alphabeta.set_state(std::mem::take(&mut self.wld_state));
+            let result = alphabeta.search(root_board, n_empties);
+            self.wld_state = alphabeta.take_state();
+            if pondering || !alphabeta.is_aborted() {
+                return RootSearch {
+                    scores: best_leaves_root_scores(&alphabeta, 1.0),
+                    result: result.map(|result| result.with_source(ResultSource::WldSolver)),
+                    aborted: alphabeta.is_aborted(),
+                };
+            }
+        }
+
+        // pondering deepens from the shallowest depth, so that each step resumes from the
+        // entries of the previous ones, and without the time budget
+        let max_depth = self.config.depth(i_step);
+        let min_depth = if pondering || self.config.time_budget_ms.is_some() {
+            0
+        } else {
+            max_depth
+        };
+        // the rest of the time budget when a solver has not finished in time
+        let time_budget_ms = time_budget_ms
+            .map(|time_budget_ms| time_budget_ms - (utils::now_millis() - started_at));
+
+        #[cfg(feature = "quantized-weights")]
+        if self.config.evaluator == EvaluatorType::Pattern {
+            let weights = self.quantized_weights.clone();
+            let scale = weights.scale();
+            let create_alphabeta = |max_n_leaves| {
+                let weights = weights.clone();
+                let mut alphabeta =
+                    AlphaBeta::create(max_n_leaves, move |board: &Board, player: &Player| {
+                        weights.score(board, player)
+                    });
+                alphabeta.disable_logging();
+                alphabeta.enable_parity_ordering();
+                alphabeta
+            };
+            return iterative_deepening(
+                root_board,
+                min_depth..=max_depth,
+                time_budget_ms,
+                max_n_leaves,
+                scale,
+                create_alphabeta,
+                &mut self.quantized_midgame_state,
+            );
+        }
+
+        let evaluator = self.config.evaluator;
+        let weights = self.weights.clone();
+        let create_alphabeta = |max_n_leaves| {
+            let mut alphabeta = AlphaBeta::create(
+                max_n_leaves,
+                evaluator.evaluate_board_func(weights.as_ref()),
+            );
+            alphabeta.disable_logging();
+            if evaluator == EvaluatorType::Numdisk {
+                alphabeta.enable_stability_cutoff();
+            }
+            alphabeta.enable_parity_ordering();
+            alphabeta
+        };
+        iterative_deepening(
+            root_board,
+            min_depth..=max_depth,
+            time_budget_ms,
+            max_n_leaves,
+            1.0,
+            create_alphabeta,
+            &mut self.midgame_state,
+        )
+    }
+
+    /// Search of the root moves of `player` (see `search_root_moves`)
+    fn search(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: Option<usize>,
+    ) -> RootSearch {
+        let root_board = match player {
+            Player::First => board.clone(),
+            Player::Second => Board::reverse(board),
+        };
+        self.search_root_moves(&root_board, i_step, max_n_leaves)
+    }
+
+    /// Root move scores of `player`, with the result of the search kept for `expected_reply`
+    fn search_scores(&mut self, board: &Board, player: &Player, i_step: usize) -> Vec<(u64, f32)> {
+        let search = self.search(board, player, i_step, None);
+        self.last_result = search.result;
+        search.scores
+    }
+
+    /// The best move of `scores`, or another one by the deliberate errors and the softmax
+    fn choose_move(&mut self, scores: &[(u64, f32)]) -> Result<SearchResult, StrategyError> {
+        let chosen = if scores.len() > 1 && self.rng.next_f32() < self.config.error_rate {
+            Some(scores[1 + self.rng.next_usize(scores.len() - 1)])
+        } else {
+            softmax::sample_move(
+                scores,
+                self.config.temperature,
+                self.config.max_loss,
+                &mut self.rng,
+            )
+        };
+        match (chosen, self.last_result.clone()) {
+            (Some((put_position, score)), Some(result)) => {
+                match Square::from_bitboard(put_position) {
+                    Some(square) => Ok(result.with_move(square, score)),
+                    None => Err(StrategyError::EmptySearchResult),
+                }
+            }
+            _ => Err(StrategyError::EmptySearchResult),
+        }
+    }
+}
+
+impl Strategy for ConfiguredStrategy {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let scores = self.search_scores(board, player, i_step);
+        self.choose_move(&scores)
+    }
+
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        Ok(self.search_scores(board, player, i_step))
+    }
+
+    fn new_game(&mut self) {
+        self.exact_state = SearchState::default();
+        self.wld_state = SearchState::default();
+        self.midgame_state = SearchState::default();
+        #[cfg(feature = "quantized-weights")]
+        {
+            self.quantized_midgame_state = SearchState::default();
+        }
+        self.last_result = None;
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        Ok(!self
+            .search(board, player, i_step, Some(max_n_leaves))
+            .aborted)
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        self.last_result
+            .as_ref()
+            .and_then(|result| result.principal_variation.get(1).copied().flatten())
+    }
+
+    fn win_probability_scale(&self) -> f32 {
+        self.config.evaluator.win_probability_scale()
+    }
+}
+
+/// `ConfiguredStrategy` with the weights file of the config, wrapped by a `BookStrategy` when
+/// the config has a book file
+pub fn build_strategy(config: StrategyConfig, rng: Rng) -> Result<Box<dyn Strategy>, String> {
+    let mut strategy = ConfiguredStrategy::create(config.clone(), rng.clone());
+    if let Some(path) = &config.weights {
+        if config.evaluator != EvaluatorType::Pattern {
+            return Err(String::from(
+                "weights are used only by the pattern evaluator",
+            ));
+        }
+        strategy.set_weights(PatternWeights::load(path)?);
+    }
+    match config.book {
+        Some(BookConfig {
+            path: Some(path),
+            randomness,
+        }) => {
+            let book = Book::load(&path)?;
+            let book_rng = Rng::create(rng.clone().next_u64());
+            Ok(Box::new(BookStrategy::create(
+                book, strategy, randomness, book_rng,
+            )))
+        }
+        _ => Ok(Box::new(strategy)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_algorithm::Bound;
+    use crate::strategy::config::*;
+    use crate::test_utils::fixture_board;
+
+    #[test]
+    fn depth_schedule() {
+        let config = StrategyConfig::default();
+        assert_eq!(config.depth(0), 7);
+        assert_eq!(config.depth(41), 9);
+        assert_eq!(config.depth(60), 13);
+        let config = StrategyConfig {
+            depth_schedule: vec![],
+            ..StrategyConfig::default()
+        };
+        assert_eq!(config.depth(30), 0);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let config = StrategyConfig::from_json(
+            r#"{"depth_schedule": [[0, 2], [40, 4]], "time_budget_ms": 500, "wld_empties": 12,
+                "evaluator": "pattern", "book": {"path": null, "randomness": 1.5}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.depth(50), 4);
+        assert_eq!(config.time_budget_ms, Some(500.0));
+        assert_eq!(config.exact_empties, 0);
+        assert_eq!(config.evaluator, EvaluatorType::Pattern);
+        assert_eq!(config.book.as_ref().unwrap().randomness, 1.5);
+        assert_eq!(StrategyConfig::from_json(&config.to_json()), Ok(config));
+
+        assert_eq!(
+            StrategyConfig::from_json("{}"),
+            Ok(StrategyConfig::default())
+        );
+        assert!(StrategyConfig::from_json(r#"{"depht_schedule": []}"#).is_err());
+        assert!(StrategyConfig::from_json(r#"{"evaluator": "neural"}"#).is_err());
+    }
+
+    #[test]
+    fn exact_and_wld_solving() {
+        let board = fixture_board();
+        let mut exact = ConfiguredStrategy::create(
+            StrategyConfig {
+                exact_empties: 10,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        );
+        let result = exact.get_next_move(&board, &Player::First, 52).unwrap();
+        assert_eq!(result.put_position(), 1 << 6); // g1
+        assert_eq!(result.score, 38.0);
+        assert_eq!(result.source, ResultSource::ExactSolver);
+        assert_eq!(result.depth, 8); // the number of empty squares
+        assert!(result.n_nodes > 0);
+
+        let mut wld = ConfiguredStrategy::create(
+            StrategyConfig {
+                wld_empties: 10,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        );
+        let scores = wld.root_move_scores(&board, &Player::First, 52).unwrap();
+        assert_eq!(scores[0].1, 1.0);
+        assert!(scores
+            .iter()
+            .all(|(_, score)| [-1.0, 0.0, 1.0].contains(score)));
+        let result = wld.get_next_move(&board, &Player::First, 52).unwrap();
+        assert_eq!(result.source, ResultSource::WldSolver);
+    }
+
+    #[test]
+    fn wld_solving_of_draw() {
+        // a1 draws, and the other moves lose
+        let board = Board::create_from_str(
+            "
+            - x x o o - - x
+            o x x o o o o o
+            o o x x x x o o
+            o - x o x o o o
+            o x o x x x o x
+            o x x x x o x x
+            o x x x x x x x
+            x x x o - o - x
+            ",
+        );
+        let config = StrategyConfig {
+            wld_empties: 10,
+            ..StrategyConfig::default()
+        };
+        for (board, player) in [
+            (board.clone(), Player::First),
+            (Board::reverse(&board), Player::Second),
+        ] {
+            let mut wld = ConfiguredStrategy::create(config.clone(), Rng::create(0));
+            let scores = wld.root_move_scores(&board, &player, 54).unwrap();
+            assert_eq!(scores[0], (1, 0.0));
+            assert!(scores[1..].iter().all(|(_, score)| *score == -1.0));
+            let result = wld.get_next_move(&board, &player, 54).unwrap();
+            assert_eq!((result.put_position(), result.score), (1, 0.0));
+        }
+    }
+
+    #[test]
+    fn time_budget() {
+        let mut strategy = ConfiguredStrategy::create(
+            StrategyConfig {
+                depth_schedule: vec![(0, 20)],
+                time_budget_ms: Some(0.0),
+                evaluator: EvaluatorType::Heuristic,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        );
+        // only the depth 0 is searched
+        let board = crate::board::new_board();
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert!(result.best_move.is_some());
+        assert_eq!((result.depth, result.source), (0, ResultSource::Search));
+    }
+
+    #[test]
+    fn abort_keeps_last_finished_depth() {
+        let config = StrategyConfig {
+            depth_schedule: vec![(0, 8)],
+            evaluator: EvaluatorType::Heuristic,
+            ..StrategyConfig::default()
+        };
+        let mut strategy = ConfiguredStrategy::create(config, Rng::create(0));
+        let board = crate::board::new_board();
+        let search = strategy.search(&board, &Player::First, 0, Some(2000));
+        assert!(search.aborted);
+        let result = search.result.unwrap();
+        assert!(result.best_move.is_some());
+        assert!(result.depth < 8);
+        assert_eq!(result.bound, Bound::Exact);
+        assert_eq!(search.scores.len(), 4);
+    }
+
+    #[test]
+    fn solver_out_of_time_budget() {
+        let board = Board::create_from_str(
+            "
+            x x x x x x x x
+            o x x x x o x x
+            o o x x x o - -
+            o o o x x o o -
+            o o o x x o - -
+            o o o o x x - -
+            o o o o x - - -
+            o o o - x - - -
+            ",
+        );
+        let mut strategy = ConfiguredStrategy::create(
+            StrategyConfig {
+                depth_schedule: vec![(0, 2)],
+                time_budget_ms: Some(0.0),
+                exact_empties: 20,
+                evaluator: EvaluatorType::Heuristic,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        );
+        // the midgame search of the first depth is done instead
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert!(result.best_move.is_some());
+        assert_eq!((result.depth, result.source), (0, ResultSource::Search));
+    }
+
+    #[test]
+    fn ponder_by_bounded_steps() {
+        let midgame_board = Board::create_from_str(
+            "
+            x x x x x x x x
+            o x x x x o x x
+            o o x x x o - -
+            o o o x x o o -
+            o o o x x o - -
+            o o o o x x - -
+            o o o o x - - -
+            o o o - x - - -
+            ",
+        );
+        let midgame = StrategyConfig {
+            depth_schedule: vec![(0, 5)],
+            evaluator: EvaluatorType::Heuristic,
+            ..StrategyConfig::default()
+        };
+        let exact = StrategyConfig {
+            exact_empties: 14,
+            ..StrategyConfig::default()
+        };
+        for (config, board, max_n_leaves) in
+            [(midgame, midgame_board, 1000), (exact, fixture_board(), 10)]
+        {
+            let mut strategy = ConfiguredStrategy::create(config.clone(), Rng::create(0));
+            let mut n_steps = 1;
+            while !strategy
+                .ponder(&board, &Player::First, 0, max_n_leaves)
+                .unwrap()
+            {
+                n_steps += 1;
+            }
+            assert!(n_steps > 1);
+            // the search continues from the pondered entries, and the random numbers of the
+            // moves are not drawn by pondering
+            let pondered = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+            let searched = ConfiguredStrategy::create(config, Rng::create(0))
+                .get_next_move(&board, &Player::First, 0)
+                .unwrap();
+            assert_eq!(
+                (pondered.best_move, pondered.score, pondered.source),
+                (searched.best_move, searched.score, searched.source)
+            );
+            assert!(pondered.n_nodes < searched.n_nodes);
+        }
+    }
+
+    #[cfg(feature = "quantized-weights")]
+    #[test]
+    fn pattern_search_with_integer_scores() {
+        let mut strategy = ConfiguredStrategy::create(
+            StrategyConfig {
+                depth_schedule: vec![(0, 3)],
+                evaluator: EvaluatorType::Pattern,
+                ..StrategyConfig::default()
+            },
+            Rng::create(0),
+        );
+        let board = crate::board::new_board();
+        let scale = QuantizedPatternWeights::default().scale();
+        let scores = strategy
+            .root_move_scores(&board, &Player::First, 0)
+            .unwrap();
+        assert!(!scores.is_empty());
+        for (_, score) in scores {
+            assert_eq!((score / scale).round() * scale, score);
+        }
+        assert!(strategy.quantized_midgame_state.principal_variation().len() >= 3);
+    }
+
+    #[test]
+    fn build_strategy_without_book_file() {
+        let config = StrategyConfig {
+            book: Some(BookConfig {
+                path: Some("/nonexistent/book.bin".to_string()),
+                randomness: 0.0,
+            }),
+            ..StrategyConfig::default()
+        };
+        assert!(build_strategy(config, Rng::create(0)).is_err());
+        assert!(build_strategy(StrategyConfig::default(), Rng::create(0)).is_ok());
+    }
+
+    #[test]
+    fn build_strategy_with_weights_file() {
+        let path = std::env::temp_dir().join("reversi-config-weights.txt");
+        let path = path.to_str().unwrap().to_string();
+        PatternWeights::default().save(&path).unwrap();
+        let config = StrategyConfig {
+            depth_schedule: vec![(0, 2)],
+            evaluator: EvaluatorType::Pattern,
+            weights: Some(path.clone()),
+            ..StrategyConfig::default()
+        };
+        let board = crate::board::new_board();
+        let result = build_strategy(config.clone(), Rng::create(0))
+            .unwrap()
+            .get_next_move(&board, &Player::First, 0)
+            .unwrap();
+        let builtin = ConfiguredStrategy::create(
+            StrategyConfig {
+                weights: None,
+                ..config.clone()
+            },
+            Rng::create(0),
+        )
+        .get_next_move(&board, &Player::First, 0)
+        .unwrap();
+        // the saved weights are the built-in ones
+        assert_eq!(
+            (result.principal_variation, result.score),
+            (builtin.principal_variation, builtin.score)
+        );
+
+        let numdisk = StrategyConfig {
+            evaluator: EvaluatorType::Numdisk,
+            ..config
+        };
+        assert!(build_strategy(numdisk, Rng::create(0)).is_err());
+    }
+}
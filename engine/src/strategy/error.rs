@@ -0,0 +1,46 @@
+use std::fmt;
+
+use crate::board::Square;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrategyError {
+    /// The player to move has no legal moves, so the strategy must not be asked for a move
+    NoLegalMoves,
+    /// The search found no move, e.g. when it was stopped before searching any
+    EmptySearchResult,
+    /// A strategy returned a move which is not legal
+    IllegalMove(Square),
+    /// `Strategy::ponder` of a strategy without search state
+    PonderingNotSupported,
+    /// Errors of the components of a strategy, e.g. reading a book file
+    Other(String),
+}
+
+impl fmt::Display for StrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrategyError::NoLegalMoves => write!(f, "No legal moves"),
+            StrategyError::EmptySearchResult => {
+                write!(f, "Result of alpha-beta pruning search is empty")
+            }
+            StrategyError::IllegalMove(square) => write!(f, "Illegal move {}", square),
+            StrategyError::PonderingNotSupported => write!(f, "Pondering is not supported"),
+            StrategyError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StrategyError {}
+
+/// So that `?` works in functions returning `Result<_, String>`, e.g. `arena::play_game`
+impl From<StrategyError> for String {
+    fn from(error: StrategyError) -> String {
+        error.to_string()
+    }
+}
+
+impl From<String> for StrategyError {
+    fn from(msg: String) -> StrategyError {
+        StrategyError::Other(msg)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::strategy::config::BookConfig;
+use crate::strategy::config::EvaluatorType;
+use crate::strategy::config::StrategyConfig;
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 10;
+
+/// Configuration of the playing strength from 1 (beginner) to 10 (strongest). The book file is
+/// left empty since it is given by `Game::load_book`.
+pub fn config(level: u8) -> Result<StrategyConfig, String> {
+    let (depth, time_budget_ms, exact_empties, evaluator, use_book, error_rate) = match level {
+        1 => (0, 100.0, 0, EvaluatorType::Numdisk, false, 0.3),
+        2 => (1, 100.0, 4, EvaluatorType::Numdisk, false, 0.2),
+        3 => (1, 200.0, 6, EvaluatorType::Heuristic, false, 0.15),
+        4 => (2, 200.0, 8, EvaluatorType::Heuristic, false, 0.1),
+        5 => (2, 300.0, 8, EvaluatorType::Pattern, true, 0.05),
+        6 => (3, 500.0, 10, EvaluatorType::Pattern, true, 0.02),
+        7 => (4, 700.0, 10, EvaluatorType::Pattern, true, 0.0),
+        8 => (5, 1000.0, 12, EvaluatorType::Pattern, true, 0.0),
+        9 => (6, 2000.0, 12, EvaluatorType::Pattern, true, 0.0),
+        10 => (8, 4000.0, 14, EvaluatorType::Pattern, true, 0.0),
+        _ => {
+            return Err(format!(
+                "level must be from {} to {}: {}",
+                MIN_LEVEL, MAX_LEVEL, level
+            ));
+        }
+    };
+    // the weaker levels choose human-like moves from the softmax of the scores
+    let (temperature, max_loss) = match level {
+        1 => (4.0, 16.0),
+        2 => (3.0, 12.0),
+        3 => (2.0, 8.0),
+        4 => (1.0, 6.0),
+        _ => (0.0, 0.0),
+    };
+    Ok(StrategyConfig {
+        depth_schedule: vec![(0, depth)],
+        time_budget_ms: Some(time_budget_ms),
+        wld_empties: 0,
+        exact_empties,
+        evaluator,
+        weights: None,
+        book: use_book.then_some(BookConfig {
+            path: None,
+            randomness: 0.0,
+        }),
+        error_rate,
+        temperature,
+        max_loss,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Player;
+    use crate::rng::Rng;
+    use crate::strategy::config::ConfiguredStrategy;
+    use crate::strategy::level::*;
+    use crate::strategy::Strategy;
+    use crate::test_utils::fixture_board;
+
+    #[test]
+    fn levels() {
+        assert!(config(0).is_err());
+        assert!(config(11).is_err());
+        let configs: Vec<StrategyConfig> = (MIN_LEVEL..=MAX_LEVEL)
+            .map(|level| config(level).unwrap())
+            .collect();
+        // stronger levels search deeper with fewer errors
+        for pair in configs.windows(2) {
+            assert!(pair[0].depth(0) <= pair[1].depth(0));
+            assert!(pair[0].time_budget_ms <= pair[1].time_budget_ms);
+            assert!(pair[0].exact_empties <= pair[1].exact_empties);
+            assert!(pair[0].error_rate >= pair[1].error_rate);
+            assert!(pair[0].temperature >= pair[1].temperature);
+        }
+        assert!(configs[0].book.is_none());
+        assert!(configs[9].book.is_some());
+    }
+
+    #[test]
+    fn exact_endgame() {
+        let mut strategy = ConfiguredStrategy::create(config(10).unwrap(), Rng::create(0));
+        let result = strategy
+            .get_next_move(&fixture_board(), &Player::First, 52)
+            .unwrap();
+        assert_eq!(result.put_position(), 1 << 6); // g1
+        assert_eq!(result.score, 38.0);
+    }
+
+    #[test]
+    fn deliberate_errors() {
+        let board = crate::board::new_board();
+        let mut weak = config(1).unwrap();
+        weak.error_rate = 1.0;
+        let mut strategy = ConfiguredStrategy::create(weak, Rng::create(0));
+        // all moves are symmetric, so the scores are the same but the moves differ
+        let moves: Vec<u64> = (0..20)
+            .map(|_| {
+                strategy
+                    .get_next_move(&board, &Player::First, 0)
+                    .unwrap()
+                    .put_position()
+            })
+            .collect();
+        assert!(moves.iter().any(|&m| m != moves[0]));
+
+        let mut strategy = ConfiguredStrategy::create(config(7).unwrap(), Rng::create(0));
+        assert!(strategy
+            .get_next_move(&board, &Player::Second, 0)
+            .unwrap()
+            .best_move
+            .is_some());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::rng::Rng;
+use crate::search_algorithm::SearchResult;
+use crate::strategy::error::StrategyError;
+use crate::strategy::Strategy;
+
+/// Move sampled with probability proportional to `exp((score - best_score) / temperature)` among
+/// the moves losing at most `max_loss` discs from the best one. A temperature of 0 or less
+/// chooses the best move.
+pub fn sample_move(
+    scores: &[(u64, f32)],
+    temperature: f32,
+    max_loss: f32,
+    rng: &mut Rng,
+) -> Option<(u64, f32)> {
+    let best = scores
+        .iter()
+        .copied()
+        .reduce(|a, b| if b.1 > a.1 { b } else { a })?;
+    if temperature <= 0.0 {
+        return Some(best);
+    }
+
+    let candidates: Vec<((u64, f32), f32)> = scores
+        .iter()
+        .filter(|(_, score)| best.1 - score <= max_loss)
+        .map(|&m| (m, ((m.1 - best.1) / temperature).exp()))
+        .collect();
+    let total: f32 = candidates.iter().map(|(_, weight)| weight).sum();
+    let mut threshold = rng.next_f32() * total;
+    for &(m, weight) in &candidates {
+        if threshold < weight {
+            return Some(m);
+        }
+        threshold -= weight;
+    }
+    // rounding errors
+    Some(best)
+}
+
+/// Samples a move from the softmax of the root move scores of the wrapped strategy, so that
+/// weakened play loses a little here and there rather than blundering at random.
+pub struct SoftmaxStrategy<S: Strategy> {
+    strategy: S,
+    temperature: f32,
+    max_loss: f32,
+    rng: Rng,
+}
+
+impl<S: Strategy> SoftmaxStrategy<S> {
+    pub fn create(strategy: S, temperature: f32, max_loss: f32, rng: Rng) -> SoftmaxStrategy<S> {
+        SoftmaxStrategy {
+            strategy,
+            temperature,
+            max_loss,
+            rng,
+        }
+    }
+}
+
+impl<S: Strategy> Strategy for SoftmaxStrategy<S> {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let scores = self.strategy.root_move_scores(board, player, i_step)?;
+        let sampled = sample_move(&scores, self.temperature, self.max_loss, &mut self.rng);
+        // the search of the wrapped strategy gives the depth and the source of the result, and
+        // its principal variation when the best move is sampled
+        let result = self.strategy.get_next_move(board, player, i_step)?;
+        match sampled.and_then(|(put_position, score)| {
+            Square::from_bitboard(put_position).map(|square| (square, score))
+        }) {
+            Some((square, score)) => Ok(result.with_move(square, score)),
+            // no legal moves
+            None => Ok(result),
+        }
+    }
+
+    fn root_move_scores(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<Vec<(u64, f32)>, StrategyError> {
+        self.strategy.root_move_scores(board, player, i_step)
+    }
+
+    fn new_game(&mut self) {
+        self.strategy.new_game()
+    }
+
+    fn ponder(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+        max_n_leaves: usize,
+    ) -> Result<bool, StrategyError> {
+        self.strategy.ponder(board, player, i_step, max_n_leaves)
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        self.strategy.expected_reply()
+    }
+
+    fn win_probability_scale(&self) -> f32 {
+        self.strategy.win_probability_scale()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_algorithm::ResultSource;
+    use crate::strategy::softmax::*;
+    use crate::test_utils::fixture_board;
+    use crate::test_utils::numdisk_strategy;
+
+    #[test]
+    fn sample_within_max_loss() {
+        let scores = [(1, 10.0), (2, 8.0), (4, 0.0)];
+        let mut rng = Rng::create(0);
+        assert_eq!(sample_move(&scores, 0.0, 100.0, &mut rng), Some((1, 10.0)));
+        assert_eq!(sample_move(&[], 1.0, 100.0, &mut rng), None);
+
+        let counts = (0..1000).fold([0; 3], |mut counts, _| {
+            match sample_move(&scores, 2.0, 5.0, &mut rng) {
+                Some((1, _)) => counts[0] += 1,
+                Some((2, _)) => counts[1] += 1,
+                _ => counts[2] += 1,
+            }
+            counts
+        });
+        // exp(-1) = 0.37, so about 27% of the moves lose 2 discs
+        assert!(counts[0] > counts[1] && counts[1] > 150);
+        assert_eq!(counts[2], 0);
+    }
+
+    #[test]
+    fn reproducible_with_seed() {
+        let board = crate::board::new_board();
+        let play = |seed: u64| -> Vec<u64> {
+            let mut strategy =
+                SoftmaxStrategy::create(numdisk_strategy(1), 10.0, 64.0, Rng::create(seed));
+            (0..10)
+                .map(|_| {
+                    strategy
+                        .get_next_move(&board, &Player::First, 0)
+                        .unwrap()
+                        .put_position()
+                })
+                .collect()
+        };
+        assert_eq!(play(1), play(1));
+        // the 4 first moves are symmetric and have the same score
+        assert!(play(1).iter().any(|&m| m != play(1)[0]));
+    }
+
+    #[test]
+    fn keep_result_of_wrapped_strategy() {
+        let board = crate::board::new_board();
+        let mut strategy = SoftmaxStrategy::create(numdisk_strategy(3), 0.0, 0.0, Rng::create(0));
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert_eq!((result.depth, result.source), (3, ResultSource::Search));
+        assert_eq!(result.principal_variation[0], result.best_move);
+    }
+
+    #[test]
+    fn default_root_move_scores() {
+        let board = fixture_board();
+        let mut strategy = crate::strategy::NumdiskLookaheadStrategy::default();
+        let scores = strategy
+            .root_move_scores(&board, &Player::First, 52)
+            .unwrap();
+        assert_eq!(scores[0], (1 << 6, 38.0)); // g1
+        assert_eq!(
+            scores.len(),
+            board.get_all_legal_moves(&Player::First).len()
+        );
+    }
+}
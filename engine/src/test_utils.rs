@@ -0,0 +1,30 @@
+use crate::board::Board;
+use crate::board::Player;
+use crate::strategy::LookaheadStrategy;
+
+/// Puzzle 99 in Brian Rose, "Othello: A Minute to Learn...A Lifetime to Master", where black
+/// to move wins by 38 discs with g1
+pub fn fixture_board() -> Board {
+    Board::create_from_str(
+        "
+        - x x x x x - o
+        - - x x x x x o
+        o x x x o x o o
+        o o o x x x o o
+        o x x x x x o o
+        o x x x x x o o
+        o - x x x x - o
+        - x x x x x x -
+        ",
+    )
+}
+
+/// Search of `depth` plies evaluated by the disc difference, without logging
+pub fn numdisk_strategy(depth: u64) -> LookaheadStrategy {
+    let mut strategy =
+        LookaheadStrategy::create(depth, 10000, |board: &Board, player: &Player| -> f32 {
+            board.score_numdisk(player)
+        });
+    strategy.disable_logging();
+    strategy
+}
@@ -0,0 +1,526 @@
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::arena;
+use crate::arena::GameRecord;
+use crate::arena::MatchResult;
+use crate::rng::Rng;
+use crate::strategy::config;
+use crate::strategy::config::StrategyConfig;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Engine {
+    pub name: String,
+    pub config: StrategyConfig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentMode {
+    /// Every pair of engines
+    RoundRobin,
+    /// The first engine against each of the others
+    Gauntlet,
+}
+
+/// Engines of a tournament and how they are paired, e.g.
+///
+/// ```json
+/// {"mode": "round_robin", "n_openings": 50, "engines": [
+///   {"name": "numdisk", "config": {"depth_schedule": [[0, 4]]}},
+///   {"name": "pattern", "config": {"depth_schedule": [[0, 4]], "evaluator": "pattern"}}]}
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TournamentConfig {
+    pub engines: Vec<Engine>,
+    pub mode: TournamentMode,
+    /// Each pair of engines plays each of `arena::standard_openings(n_openings)` twice
+    pub n_openings: usize,
+}
+
+impl TournamentConfig {
+    pub fn from_json(json: &str) -> Result<TournamentConfig, String> {
+        serde_json::from_str(json).map_err(|e| format!("invalid tournament config: {}", e))
+    }
+
+    pub fn load(path: &str) -> Result<TournamentConfig, String> {
+        let json =
+            std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        TournamentConfig::from_json(&json)
+    }
+}
+
+/// Finished game, saved as a line of JSON in the results file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// Names of the engines playing black and white
+    pub first: String,
+    pub second: String,
+    /// Index of the opening in the openings of the tournament
+    pub i_opening: usize,
+    /// Final disc difference from black
+    pub score: f32,
+}
+
+impl GameResult {
+    /// Points of black: 1 for a win and 0.5 for a draw
+    fn first_points(&self) -> f64 {
+        if self.score > 0.0 {
+            1.0
+        } else if self.score == 0.0 {
+            0.5
+        } else {
+            0.0
+        }
+    }
+}
+
+pub struct Tournament {
+    config: TournamentConfig,
+    results: Vec<GameResult>,
+}
+
+impl Tournament {
+    pub fn create(config: TournamentConfig) -> Result<Tournament, String> {
+        if config.engines.len() < 2 {
+            return Err(String::from("a tournament needs at least two engines"));
+        }
+        let names: HashSet<&String> = config.engines.iter().map(|e| &e.name).collect();
+        if names.len() != config.engines.len() {
+            return Err(String::from("engine names must be unique"));
+        }
+        Ok(Tournament {
+            config,
+            results: Vec::new(),
+        })
+    }
+
+    pub fn results(&self) -> &[GameResult] {
+        &self.results
+    }
+
+    /// Add the games saved by `play_pairing`, so that an interrupted tournament resumes where it
+    /// stopped. A missing file means no games. Engines are identified by their names only, and
+    /// games of engines not in the tournament or already added are ignored.
+    ///
+    /// A last line without a newline may have been cut by the interruption, so it is removed from
+    /// the file and its game is played again.
+    pub fn load_results(&mut self, path: &str) -> Result<(), String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("cannot read {}: {}", path, e)),
+        };
+        let complete_len = text.rfind('\n').map_or(0, |i| i + 1);
+        if complete_len < text.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_len(complete_len as u64))
+                .map_err(|e| format!("cannot write {}: {}", path, e))?;
+        }
+        let mut loaded: HashSet<(String, String, usize)> = self
+            .results
+            .iter()
+            .map(|r| (r.first.clone(), r.second.clone(), r.i_opening))
+            .collect();
+        for line in text[..complete_len].lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result: GameResult = serde_json::from_str(line)
+                .map_err(|e| format!("invalid game result {}: {}", line, e))?;
+            if self.engine_index(&result.first).is_some()
+                && self.engine_index(&result.second).is_some()
+                && loaded.insert((
+                    result.first.clone(),
+                    result.second.clone(),
+                    result.i_opening,
+                ))
+            {
+                self.results.push(result);
+            }
+        }
+        Ok(())
+    }
+
+    fn engine_index(&self, name: &str) -> Option<usize> {
+        self.config.engines.iter().position(|e| e.name == name)
+    }
+
+    /// Pairs of engine indices to play
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let n_engines = self.config.engines.len();
+        match self.config.mode {
+            TournamentMode::RoundRobin => (0..n_engines)
+                .flat_map(|a| (a + 1..n_engines).map(move |b| (a, b)))
+                .collect(),
+            TournamentMode::Gauntlet => (1..n_engines).map(|b| (0, b)).collect(),
+        }
+    }
+
+    /// Openings for which the pair of games of `a` and `b` has not been finished
+    pub fn missing_openings(&self, a: usize, b: usize) -> Vec<usize> {
+        let (name_a, name_b) = (&self.config.engines[a].name, &self.config.engines[b].name);
+        let played = |first: &String, second: &String, i_opening: usize| {
+            self.results
+                .iter()
+                .any(|r| r.first == *first && r.second == *second && r.i_opening == i_opening)
+        };
+        (0..self.config.n_openings)
+            .filter(|&i| !played(name_a, name_b, i) || !played(name_b, name_a, i))
+            .collect()
+    }
+
+    /// Play the missing games of `a` and `b` (see `arena::run_match`), and append the games of
+    /// each opening to `results_path` as soon as they are finished. `None` when all the games had
+    /// been played.
+    pub fn play_pairing(
+        &mut self,
+        a: usize,
+        b: usize,
+        openings: &[Vec<u64>],
+        n_threads: usize,
+        results_path: &str,
+    ) -> Result<Option<MatchResult>, String> {
+        let missing = self.missing_openings(a, b);
+        if missing.is_empty() {
+            return Ok(None);
+        }
+        let missing_openings: Vec<Vec<u64>> =
+            missing.iter().map(|&i| openings[i].clone()).collect();
+        let (engine_a, engine_b) = (&self.config.engines[a], &self.config.engines[b]);
+        // the random numbers are seeded by the index of the opening in `openings`, so that the
+        // games do not depend on which ones were missing
+        let create_a = |i: usize| {
+            config::build_strategy(engine_a.config.clone(), Rng::create(2 * missing[i] as u64))
+        };
+        let create_b = |i: usize| {
+            config::build_strategy(
+                engine_b.config.clone(),
+                Rng::create(2 * missing[i] as u64 + 1),
+            )
+        };
+        // a plays black first for each opening
+        let game_pair = |i: usize, a_first: &GameRecord, b_first: &GameRecord| {
+            [(engine_a, engine_b, a_first), (engine_b, engine_a, b_first)].map(
+                |(first, second, record)| GameResult {
+                    first: first.name.clone(),
+                    second: second.name.clone(),
+                    i_opening: missing[i],
+                    score: record.score(),
+                },
+            )
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(results_path)
+            .map_err(|e| format!("cannot write {}: {}", results_path, e))?;
+        let file = Mutex::new(file);
+        // both lines are written at once, so that an interruption cuts only the last line
+        let save_pair = |i: usize, a_first: &GameRecord, b_first: &GameRecord| {
+            let lines: String = game_pair(i, a_first, b_first)
+                .iter()
+                .map(|game| format!("{}\n", serde_json::to_string(game).unwrap()))
+                .collect();
+            file.lock()
+                .unwrap()
+                .write_all(lines.as_bytes())
+                .map_err(|e| format!("cannot write {}: {}", results_path, e))
+        };
+        let (result, records) = arena::run_match_with_callback(
+            &create_a,
+            &create_b,
+            &missing_openings,
+            n_threads,
+            &save_pair,
+        )?;
+        for (i, pair) in records.chunks(2).enumerate() {
+            self.results.extend(game_pair(i, &pair[0], &pair[1]));
+        }
+        Ok(Some(result))
+    }
+
+    /// (points, games) of each engine against each engine
+    fn scores(&self) -> Vec<Vec<(f64, usize)>> {
+        let n_engines = self.config.engines.len();
+        let mut scores = vec![vec![(0.0, 0); n_engines]; n_engines];
+        for result in &self.results {
+            let (Some(first), Some(second)) = (
+                self.engine_index(&result.first),
+                self.engine_index(&result.second),
+            ) else {
+                continue;
+            };
+            scores[first][second].0 += result.first_points();
+            scores[first][second].1 += 1;
+            scores[second][first].0 += 1.0 - result.first_points();
+            scores[second][first].1 += 1;
+        }
+        scores
+    }
+
+    /// Table of the points of the engine of each row against the engine of each column, e.g.
+    ///
+    /// ```text
+    /// name        a         b        total
+    /// a           -         7.5/10   7.5/10
+    /// b           2.5/10    -        2.5/10
+    /// ```
+    pub fn crosstable(&self) -> String {
+        let scores = self.scores();
+        let width = self
+            .config
+            .engines
+            .iter()
+            .map(|e| e.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(10);
+        let mut table = format!("{:width$}", "name");
+        for engine in &self.config.engines {
+            table += &format!("  {:width$}", engine.name);
+        }
+        table += "  total\n";
+        for (i, engine) in self.config.engines.iter().enumerate() {
+            table += &format!("{:width$}", engine.name);
+            for (j, (points, games)) in scores[i].iter().enumerate() {
+                let cell = if i == j {
+                    String::from("-")
+                } else {
+                    format!("{}/{}", points, games)
+                };
+                table += &format!("  {:width$}", cell);
+            }
+            let points: f64 = scores[i].iter().map(|s| s.0).sum();
+            let games: usize = scores[i].iter().map(|s| s.1).sum();
+            table += &format!("  {}/{}\n", points, games);
+        }
+        table
+    }
+
+    /// Ratings of the engines in descending order, see `bradley_terry_ratings`
+    pub fn ratings(&self) -> Vec<(String, f64)> {
+        let ratings = bradley_terry_ratings(&self.scores(), 2.0);
+        let mut ratings: Vec<(String, f64)> = self
+            .config
+            .engines
+            .iter()
+            .map(|e| e.name.clone())
+            .zip(ratings)
+            .collect();
+        ratings.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ratings
+    }
+}
+
+/// Elo ratings with mean 0 maximizing the posterior probability of the (points, games) of each
+/// player against each player under the Bradley-Terry model, as BayesElo does. A draw counts as
+/// half a win and half a loss, and the prior is `prior_draws` virtual draws of each player against
+/// a player rated 0, which keeps the ratings finite when a player wins or loses all its games.
+/// Solved by the minorization-maximization algorithm of Hunter (2004).
+pub fn bradley_terry_ratings(scores: &[Vec<(f64, usize)>], prior_draws: f64) -> Vec<f64> {
+    let n_players = scores.len();
+    let mut gammas = vec![1.0; n_players];
+    for _ in 0..10000 {
+        let next: Vec<f64> = (0..n_players)
+            .map(|i| {
+                let points: f64 = scores[i].iter().map(|s| s.0).sum::<f64>() + prior_draws / 2.0;
+                let denominator: f64 = (0..n_players)
+                    .filter(|&j| j != i)
+                    .map(|j| scores[i][j].1 as f64 / (gammas[i] + gammas[j]))
+                    .sum::<f64>()
+                    + prior_draws / (gammas[i] + 1.0);
+                points / denominator
+            })
+            .collect();
+        let change = next
+            .iter()
+            .zip(&gammas)
+            .map(|(a, b)| (a / b).ln().abs())
+            .fold(0.0, f64::max);
+        gammas = next;
+        if change < 1e-10 {
+            break;
+        }
+    }
+    let elos: Vec<f64> = gammas.iter().map(|g| 400.0 * g.log10()).collect();
+    let mean = elos.iter().sum::<f64>() / n_players.max(1) as f64;
+    elos.iter().map(|elo| elo - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tournament::*;
+
+    fn engine(name: &str, depth: u64) -> Engine {
+        Engine {
+            name: name.to_string(),
+            config: StrategyConfig {
+                depth_schedule: vec![(0, depth)],
+                ..StrategyConfig::default()
+            },
+        }
+    }
+
+    fn tournament_config(mode: TournamentMode) -> TournamentConfig {
+        TournamentConfig {
+            engines: vec![engine("d1", 1), engine("d2", 2), engine("d3", 3)],
+            mode,
+            n_openings: 2,
+        }
+    }
+
+    #[test]
+    fn pairings() {
+        let round_robin = Tournament::create(tournament_config(TournamentMode::RoundRobin));
+        assert_eq!(
+            round_robin.unwrap().pairings(),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        let gauntlet = Tournament::create(tournament_config(TournamentMode::Gauntlet));
+        assert_eq!(gauntlet.unwrap().pairings(), vec![(0, 1), (0, 2)]);
+
+        let mut config = tournament_config(TournamentMode::RoundRobin);
+        config.engines[1].name = String::from("d1");
+        assert!(Tournament::create(config).is_err());
+    }
+
+    #[test]
+    fn config_from_json() {
+        let config = TournamentConfig::from_json(
+            r#"{"mode": "gauntlet", "n_openings": 2, "engines": [
+                {"name": "d1", "config": {"depth_schedule": [[0, 1]]}},
+                {"name": "d2", "config": {"depth_schedule": [[0, 2]]}},
+                {"name": "d3", "config": {"depth_schedule": [[0, 3]]}}]}"#,
+        );
+        assert_eq!(config, Ok(tournament_config(TournamentMode::Gauntlet)));
+    }
+
+    #[test]
+    fn resume_from_results_file() {
+        let path = std::env::temp_dir().join("reversi-tournament-resume.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let openings: Vec<Vec<u64>> = (0..2)
+            .map(|i| arena::random_opening(&mut Rng::create(i), 4))
+            .collect();
+
+        let mut tournament =
+            Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        tournament.load_results(path).unwrap();
+        assert_eq!(tournament.missing_openings(0, 1), vec![0, 1]);
+        let result = tournament.play_pairing(0, 1, &openings, 2, path).unwrap();
+        assert_eq!(result.unwrap().n_games(), 4);
+
+        // interrupted after the first pairing
+        let mut resumed = Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        resumed.load_results(path).unwrap();
+        // the file has the games in the order in which they are finished
+        let sorted = |results: &[GameResult]| {
+            let mut results = results.to_vec();
+            results.sort_by_key(|r| (r.i_opening, r.first.clone()));
+            results
+        };
+        assert_eq!(sorted(resumed.results()), sorted(tournament.results()));
+        assert!(resumed.missing_openings(0, 1).is_empty());
+        assert_eq!(resumed.play_pairing(0, 1, &openings, 2, path), Ok(None));
+        assert_eq!(resumed.missing_openings(0, 2), vec![0, 1]);
+        resumed.play_pairing(0, 2, &openings, 1, path).unwrap();
+        assert_eq!(resumed.results().len(), 8);
+        assert!(resumed.crosstable().contains("/4"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn interrupted_pairing_keeps_finished_openings() {
+        let path = std::env::temp_dir().join("reversi-tournament-interrupted.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        // the second opening fails since a1 is not a legal move
+        let openings = vec![arena::random_opening(&mut Rng::create(0), 4), vec![1]];
+
+        let mut tournament =
+            Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        assert!(tournament.play_pairing(0, 1, &openings, 1, path).is_err());
+        let mut resumed = Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        resumed.load_results(path).unwrap();
+        assert_eq!(resumed.results().len(), 2);
+        assert_eq!(resumed.missing_openings(0, 1), vec![1]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_results_without_cut_last_line() {
+        let path = std::env::temp_dir().join("reversi-tournament-cut.jsonl");
+        let path = path.to_str().unwrap();
+        let line = r#"{"first":"d1","second":"d2","i_opening":0,"score":4.0}"#;
+        // the same game twice, and the next one cut by an interruption
+        let complete = format!("{}\n{}\n", line, line);
+        std::fs::write(path, format!("{}{{\"first\":\"d2\",\"sec", complete)).unwrap();
+
+        let mut tournament =
+            Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        tournament.load_results(path).unwrap();
+        assert_eq!(tournament.results().len(), 1);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), complete);
+
+        // other lines are not skipped
+        std::fs::write(path, format!("{{\"first\":\n{}\n", line)).unwrap();
+        let mut tournament =
+            Tournament::create(tournament_config(TournamentMode::Gauntlet)).unwrap();
+        assert!(tournament.load_results(path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn ratings_from_scores() {
+        let mut tournament =
+            Tournament::create(tournament_config(TournamentMode::RoundRobin)).unwrap();
+        let game = |first: &str, second: &str, score: f32| GameResult {
+            first: first.to_string(),
+            second: second.to_string(),
+            i_opening: 0,
+            score,
+        };
+        tournament.results = vec![
+            game("d3", "d1", 10.0),
+            game("d1", "d3", -10.0),
+            game("d3", "d2", 0.0),
+            game("d2", "d3", -2.0),
+            game("d2", "d1", 4.0),
+            game("d1", "d2", 0.0),
+        ];
+        let ratings = tournament.ratings();
+        let names: Vec<&str> = ratings.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["d3", "d2", "d1"]);
+        let sum: f64 = ratings.iter().map(|(_, elo)| elo).sum();
+        assert!(sum.abs() < 1e-6);
+        assert!(ratings[2].1.is_finite());
+
+        let crosstable = tournament.crosstable();
+        let lines: Vec<&str> = crosstable.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[3].starts_with("d3"));
+        assert!(lines[3].ends_with("  3.5/4"));
+    }
+
+    #[test]
+    fn equal_scores_equal_ratings() {
+        let scores = vec![vec![(0.0, 0), (5.0, 10)], vec![(5.0, 10), (0.0, 0)]];
+        let ratings = bradley_terry_ratings(&scores, 2.0);
+        assert!(ratings.iter().all(|elo| elo.abs() < 1e-6));
+        // 3:1 without prior is 400 log10(3) apart
+        let scores = vec![vec![(0.0, 0), (3.0, 4)], vec![(1.0, 4), (0.0, 0)]];
+        let ratings = bradley_terry_ratings(&scores, 0.0);
+        assert!((ratings[0] - ratings[1] - 400.0 * 3f64.log10()).abs() < 1e-6);
+    }
+}
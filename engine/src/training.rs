@@ -0,0 +1,405 @@
+use std::path::Path;
+
+use crate::arena;
+use crate::arena::GameRecord;
+use crate::board;
+use crate::board::pattern_set::PatternSet;
+use crate::board::Board;
+use crate::board::Player;
+use crate::board::Square;
+use crate::console_log;
+use crate::pattern_weights::PatternWeights;
+use crate::rng::Rng;
+use crate::search_algorithm::ResultSource;
+use crate::search_algorithm::SearchResult;
+use crate::strategy;
+use crate::strategy::error::StrategyError;
+use crate::strategy::LookaheadStrategy;
+use crate::strategy::PatternScore;
+use crate::strategy::Strategy;
+use crate::win_probability;
+
+/// Plays a uniformly random legal move with probability `exploration`, otherwise follows the
+/// wrapped strategy.
+pub struct ExplorationStrategy<S: Strategy> {
+    strategy: S,
+    exploration: f32,
+    rng: Rng,
+}
+
+impl<S: Strategy> ExplorationStrategy<S> {
+    pub fn create(strategy: S, exploration: f32, rng: Rng) -> ExplorationStrategy<S> {
+        ExplorationStrategy {
+            strategy,
+            exploration,
+            rng,
+        }
+    }
+}
+
+impl<S: Strategy> Strategy for ExplorationStrategy<S> {
+    fn get_next_move(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        i_step: usize,
+    ) -> Result<SearchResult, StrategyError> {
+        let legal_moves = board.get_all_legal_moves(player);
+        if !legal_moves.is_empty() && self.rng.next_f32() < self.exploration {
+            let random_move = legal_moves[self.rng.next_usize(legal_moves.len())];
+            return Ok(SearchResult::create(
+                Square::from_bitboard(random_move),
+                0.0,
+                ResultSource::Random,
+            ));
+        }
+        self.strategy.get_next_move(board, player, i_step)
+    }
+
+    fn new_game(&mut self) {
+        self.strategy.new_game()
+    }
+
+    fn expected_reply(&self) -> Option<Square> {
+        self.strategy.expected_reply()
+    }
+}
+
+pub fn pattern_strategy(weights: PatternWeights, depth: u64) -> LookaheadStrategy<PatternScore> {
+    let mut strategy = strategy::pattern_lookahead(depth, usize::MAX, weights);
+    strategy.disable_logging();
+    strategy
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfPlayConfig {
+    pub n_games: usize,
+    /// Search depth of the engine instances
+    pub depth: u64,
+    /// Probability of a random move
+    pub exploration: f32,
+    pub lambda: f32,
+    pub learning_rate: f32,
+    /// Save a checkpoint and compare it with the previous one every `checkpoint_interval` games
+    pub checkpoint_interval: usize,
+    /// Number of openings of the match between checkpoints (each opening is played twice)
+    pub n_evaluation_openings: usize,
+    pub seed: u64,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> SelfPlayConfig {
+        SelfPlayConfig {
+            n_games: 1000,
+            depth: 1,
+            exploration: 0.1,
+            lambda: 0.7,
+            learning_rate: 0.001,
+            checkpoint_interval: 100,
+            n_evaluation_openings: 20,
+            seed: 0,
+        }
+    }
+}
+
+/// Positions after each move, with the player who played the move.
+pub fn replay_positions(record: &GameRecord) -> Vec<(Board, Player)> {
+    let mut board = board::new_board();
+    let mut player = Player::First;
+    let mut positions = Vec::new();
+    for next_move in &record.moves {
+        if let Some(next_move) = next_move {
+            board.put_and_reverse(&player, *next_move);
+            positions.push((board.clone(), player.clone()));
+        }
+        player = player.opponent();
+    }
+    positions
+}
+
+/// Offline TD(λ) update with λ-returns. Values are evaluated from the viewpoint of the player who
+/// has just moved, and the return of the last position is the final disc difference.
+pub fn td_lambda_update(
+    weights: &mut PatternWeights,
+    positions: &[(Board, Player)],
+    final_score: f32,
+    lambda: f32,
+    learning_rate: f32,
+) {
+    let features: Vec<Vec<usize>> = positions
+        .iter()
+        .map(|(board, player)| weights.feature_indices(board, player))
+        .collect();
+    let values: Vec<f32> = features
+        .iter()
+        .map(|f| f.iter().map(|&i| weights.weights()[i]).sum())
+        .collect();
+
+    let mut returns = vec![0.0; positions.len()];
+    for t in (0..positions.len()).rev() {
+        let (_, player) = &positions[t];
+        returns[t] = if t + 1 == positions.len() {
+            match player {
+                Player::First => final_score,
+                Player::Second => -final_score,
+            }
+        } else {
+            let sign = if positions[t + 1].1 == *player {
+                1.0
+            } else {
+                -1.0
+            };
+            sign * ((1.0 - lambda) * values[t + 1] + lambda * returns[t + 1])
+        };
+    }
+
+    let weights = weights.weights_mut();
+    for ((feature, value), ret) in features.iter().zip(values.iter()).zip(returns.iter()) {
+        let delta = learning_rate * (ret - value);
+        for &i in feature {
+            weights[i] += delta;
+        }
+    }
+}
+
+/// Train pattern weights by self-play, writing `checkpoint-<n_games>.txt` into `checkpoint_dir`.
+pub fn run_self_play(
+    config: &SelfPlayConfig,
+    initial_weights: PatternWeights,
+    checkpoint_dir: &Path,
+) -> Result<PatternWeights, String> {
+    let mut rng = Rng::create(config.seed);
+    let mut weights = initial_weights;
+    let mut previous_checkpoint = weights.clone();
+
+    for i_game in 0..config.n_games {
+        let mut first = ExplorationStrategy::create(
+            pattern_strategy(weights.clone(), config.depth),
+            config.exploration,
+            Rng::create(rng.next_u64()),
+        );
+        let mut second = ExplorationStrategy::create(
+            pattern_strategy(weights.clone(), config.depth),
+            config.exploration,
+            Rng::create(rng.next_u64()),
+        );
+        let record = arena::play_game(&mut first, &mut second, &[])?;
+        let positions = replay_positions(&record);
+        td_lambda_update(
+            &mut weights,
+            &positions,
+            record.score(),
+            config.lambda,
+            config.learning_rate,
+        );
+
+        let n_games = i_game + 1;
+        if n_games % config.checkpoint_interval == 0 || n_games == config.n_games {
+            let path = checkpoint_dir.join(format!("checkpoint-{}.txt", n_games));
+            weights.save(path.to_str().ok_or("invalid checkpoint path")?)?;
+
+            let openings: Vec<Vec<u64>> = (0..config.n_evaluation_openings)
+                .map(|_| arena::random_opening(&mut rng, 4))
+                .collect();
+            let result = arena::play_match(
+                &mut pattern_strategy(weights.clone(), config.depth),
+                &mut pattern_strategy(previous_checkpoint.clone(), config.depth),
+                &openings,
+            )?;
+            console_log!(
+                "games = {}, saved {}, vs previous checkpoint: {}",
+                n_games,
+                path.display(),
+                result
+            );
+            previous_checkpoint = weights.clone();
+        }
+    }
+
+    Ok(weights)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogisticConfig {
+    pub epochs: usize,
+    pub learning_rate: f32,
+    /// Scale of the logistic function, see `win_probability::DEFAULT_SCALE`
+    pub scale: f32,
+    /// Positions after the `min_step`-th to before the `max_step`-th move are used, since the
+    /// opening is covered by books and the endgame by exact search (same as `python/learn.py`)
+    pub min_step: usize,
+    pub max_step: usize,
+    pub seed: u64,
+}
+
+impl Default for LogisticConfig {
+    fn default() -> LogisticConfig {
+        LogisticConfig {
+            epochs: 10,
+            learning_rate: 0.01,
+            scale: win_probability::DEFAULT_SCALE,
+            min_step: 10,
+            max_step: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// Features of a position and the outcome (1: win, 0.5: draw, 0: loss) of the player who has just
+/// moved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogisticSample {
+    pub features: Vec<usize>,
+    pub outcome: f32,
+}
+
+/// Samples of `positions` (e.g. `ggf::replay_positions`) of a game whose final disc difference
+/// from the first player is `final_score`.
+pub fn logistic_samples(
+    pattern_set: &PatternSet,
+    positions: &[(Board, Player)],
+    final_score: f32,
+    config: &LogisticConfig,
+) -> Vec<LogisticSample> {
+    positions
+        .iter()
+        .enumerate()
+        .filter(|(i_step, _)| config.min_step < *i_step && *i_step < config.max_step)
+        .map(|(_, (board, player))| LogisticSample {
+            features: pattern_set.feature_indices(board, player),
+            outcome: match player {
+                Player::First => win_probability::outcome(final_score),
+                Player::Second => win_probability::outcome(-final_score),
+            },
+        })
+        .collect()
+}
+
+/// Mean cross entropy between the predicted win probabilities and the outcomes
+pub fn logistic_loss(weights: &PatternWeights, samples: &[LogisticSample], scale: f32) -> f32 {
+    let total: f32 = samples
+        .iter()
+        .map(|sample| {
+            let score: f32 = sample.features.iter().map(|&i| weights.weights()[i]).sum();
+            let p = win_probability::win_probability(score, scale).clamp(1e-6, 1.0 - 1e-6);
+            -(sample.outcome * p.ln() + (1.0 - sample.outcome) * (1.0 - p).ln())
+        })
+        .sum();
+    total / samples.len().max(1) as f32
+}
+
+/// Texel-style tuning: fit the weights to game outcomes by stochastic gradient descent on the
+/// logistic loss, and return the loss after each epoch.
+pub fn fit_logistic(
+    weights: &mut PatternWeights,
+    samples: &[LogisticSample],
+    config: &LogisticConfig,
+) -> Vec<f32> {
+    let mut rng = Rng::create(config.seed);
+    let mut order: Vec<usize> = (0..samples.len()).collect();
+    let mut losses = Vec::new();
+
+    for _ in 0..config.epochs {
+        // Fisher-Yates shuffle
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.next_usize(i + 1));
+        }
+
+        let table = weights.weights_mut();
+        for &i_sample in &order {
+            let sample = &samples[i_sample];
+            let score: f32 = sample.features.iter().map(|&i| table[i]).sum();
+            let p = win_probability::win_probability(score, config.scale);
+            // d(cross entropy) / d(score)
+            let gradient = (p - sample.outcome) / config.scale;
+            for &i in &sample.features {
+                table[i] -= config.learning_rate * gradient;
+            }
+        }
+        losses.push(logistic_loss(weights, samples, config.scale));
+    }
+    losses
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena;
+    use crate::board::pattern_set::PatternSet;
+    use crate::board::Player;
+    use crate::pattern_weights::PatternWeights;
+    use crate::rng::Rng;
+    use crate::training::*;
+
+    fn zero_weights() -> PatternWeights {
+        PatternWeights::create(vec![0.0; 167_265]).unwrap()
+    }
+
+    #[test]
+    fn exploration_strategy_plays_legal_moves() {
+        let mut strategy =
+            ExplorationStrategy::create(pattern_strategy(zero_weights(), 0), 1.0, Rng::create(0));
+        let board = crate::board::new_board();
+        let result = strategy.get_next_move(&board, &Player::First, 0).unwrap();
+        assert!(board.legal_moves(&Player::First) & result.put_position() != 0);
+        assert_eq!(result.source, ResultSource::Random);
+    }
+
+    #[test]
+    fn td_lambda_update_moves_values_toward_result() {
+        let mut first =
+            ExplorationStrategy::create(pattern_strategy(zero_weights(), 0), 1.0, Rng::create(1));
+        let mut second =
+            ExplorationStrategy::create(pattern_strategy(zero_weights(), 0), 1.0, Rng::create(2));
+        let record = arena::play_game(&mut first, &mut second, &[]).unwrap();
+        let positions = replay_positions(&record);
+        assert_eq!(
+            positions.len(),
+            record.moves.iter().filter(|m| m.is_some()).count()
+        );
+
+        let mut weights = zero_weights();
+        td_lambda_update(&mut weights, &positions, record.score(), 1.0, 0.001);
+
+        // with λ = 1, every position is updated toward the final result of its player
+        let (last_board, last_player) = positions.last().unwrap();
+        let expected_sign = match last_player {
+            Player::First => record.score().signum(),
+            Player::Second => -record.score().signum(),
+        };
+        assert_eq!(
+            weights.score(last_board, last_player).signum(),
+            expected_sign
+        );
+    }
+    #[test]
+    fn fit_logistic_reduces_loss() {
+        let mut first =
+            ExplorationStrategy::create(pattern_strategy(zero_weights(), 0), 1.0, Rng::create(1));
+        let mut second =
+            ExplorationStrategy::create(pattern_strategy(zero_weights(), 0), 1.0, Rng::create(2));
+        let config = LogisticConfig {
+            min_step: 0,
+            max_step: 60,
+            ..LogisticConfig::default()
+        };
+        let mut samples = Vec::new();
+        for _ in 0..4 {
+            let record = arena::play_game(&mut first, &mut second, &[]).unwrap();
+            let positions = replay_positions(&record);
+            samples.extend(logistic_samples(
+                PatternSet::standard(),
+                &positions,
+                record.score(),
+                &config,
+            ));
+        }
+        assert!(samples.iter().all(|s| [0.0, 0.5, 1.0].contains(&s.outcome)));
+
+        let mut weights = zero_weights();
+        let initial_loss = logistic_loss(&weights, &samples, config.scale);
+        assert!((initial_loss - 2.0f32.ln()).abs() < 1e-4);
+        let losses = fit_logistic(&mut weights, &samples, &config);
+        assert_eq!(losses.len(), config.epochs);
+        assert!(losses.last().unwrap() < &initial_loss);
+    }
+}
@@ -14,3 +14,18 @@ pub fn set_panic_hook() {
     #[cfg(all(target_arch = "wasm32", feature = "debug-panic-hook"))]
     console_error_panic_hook::set_once();
 }
+
+/// Milliseconds since the Unix epoch. `std::time` is not available in wasm, so `Date.now()` is
+/// used there.
+pub fn now_millis() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+    }
+}
@@ -0,0 +1,56 @@
+use wasm_bindgen::prelude::*;
+
+/// Scale of the logistic function in discs, i.e. a score of `DEFAULT_SCALE` means a win
+/// probability of 1 / (1 + e^-1) = 73%. It is used both to report evaluations and to fit pattern
+/// weights by `training::fit_logistic`, so that the weights stay in units of discs.
+pub const DEFAULT_SCALE: f32 = 7.0;
+
+/// Win probability of the player whose evaluation is `score`
+pub fn win_probability(score: f32, scale: f32) -> f32 {
+    1.0 / (1.0 + (-score / scale).exp())
+}
+
+/// Inverse of `win_probability`. The probability is clamped so that the score stays finite.
+pub fn score_from_win_probability(probability: f32, scale: f32) -> f32 {
+    let p = probability.clamp(1e-6, 1.0 - 1e-6);
+    scale * (p / (1.0 - p)).ln()
+}
+
+/// Expected outcome of a final disc difference: 1 for a win, 0.5 for a draw and 0 for a loss
+pub fn outcome(disc_difference: f32) -> f32 {
+    if disc_difference > 0.0 {
+        1.0
+    } else if disc_difference < 0.0 {
+        0.0
+    } else {
+        0.5
+    }
+}
+
+#[wasm_bindgen(js_name = winProbability)]
+pub fn win_probability_js(score: f32) -> f32 {
+    win_probability(score, DEFAULT_SCALE)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::win_probability::*;
+
+    #[test]
+    fn win_probability_is_symmetric() {
+        assert_eq!(win_probability(0.0, DEFAULT_SCALE), 0.5);
+        let p = win_probability(10.0, DEFAULT_SCALE);
+        assert!(p > 0.5);
+        assert!((p + win_probability(-10.0, DEFAULT_SCALE) - 1.0).abs() < 1e-6);
+        assert!((win_probability(DEFAULT_SCALE, DEFAULT_SCALE) - 0.7311).abs() < 1e-4);
+    }
+
+    #[test]
+    fn score_from_win_probability_is_inverse() {
+        for score in [-20.0, -3.5, 0.0, 1.0, 12.0] {
+            let p = win_probability(score, DEFAULT_SCALE);
+            assert!((score_from_win_probability(p, DEFAULT_SCALE) - score).abs() < 1e-3);
+        }
+        assert!(score_from_win_probability(1.0, DEFAULT_SCALE).is_finite());
+    }
+}
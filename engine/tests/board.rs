@@ -148,6 +148,50 @@ mod board {
             ];
             assert_eq!(actual, expected);
         }
+
+        #[test]
+        fn legal_moves_should_match_get_all_legal_moves() {
+            let board = Board::create_from_str(
+                "
+                - - - - - - - -
+                - - - - - - - -
+                - - o - - - - -
+                - - o o o - - -
+                - - - o x - - -
+                - - - - x o - -
+                - - - - - - - -
+                - - - - - - - -
+            ",
+            );
+            for player in [Player::First, Player::Second] {
+                let expected = board
+                    .get_all_legal_moves(&player)
+                    .iter()
+                    .fold(0, |acc, m| acc | m);
+                assert_eq!(board.legal_moves(&player), expected);
+            }
+        }
+
+        #[test]
+        fn neighbors() {
+            assert_eq!(Board::neighbors(1), (1 << 1) | (1 << 8) | (1 << 9));
+            assert_eq!(Board::neighbors(1 << 63), (1 << 54) | (1 << 55) | (1 << 62));
+        }
+
+        #[test]
+        fn to_diagram() {
+            let diagram = "o x - - - - - -
+- - - - - - - -
+- - - - - - - -
+- - - o x - - -
+- - - x o - - -
+- - - - - - - -
+- - - - - - - -
+- - - - - - - o";
+            let board = Board::create_from_str(diagram);
+            assert_eq!(board.to_diagram(), diagram);
+            assert_eq!(Board::create_from_str(&board.to_diagram()), board);
+        }
     }
 
     mod utils_test {
@@ -206,5 +250,4 @@ mod board {
             assert_eq!(actual, expected);
         }
     }
-
 }
@@ -3,9 +3,13 @@ import "./style.css";
 import { players, Reversi } from "./reversi";
 import { renderBoard, initializeBoard } from "./draw";
 
-const game = new Reversi();
+const level = selectLevel();
+// `?config={"depth_schedule":[[0,6]],...}` plays with a custom strategy config instead of the level
+const config = new URLSearchParams(window.location.search).get("config") ?? undefined;
+const game = new Reversi(level, config);
 initializeBoard();
 draw(game);
+loadBook(game);
 
 let boardLocked = false;
 document.querySelectorAll<HTMLElement>(".cell").forEach((cell) => {
@@ -28,6 +32,7 @@ document.querySelectorAll<HTMLElement>(".cell").forEach((cell) => {
         const [ok, r, c] = game.putAndReverseOpponent();
         if (!ok) break;
         draw(game, r, c);
+        renderWinProbability(game.getWinProbability());
         const secondShouldPlayNextTurn =
           !game.hasPossibleMove(players.first) &&
           game.hasPossibleMove(players.second);
@@ -38,19 +43,75 @@ document.querySelectorAll<HTMLElement>(".cell").forEach((cell) => {
     }
 
     boardLocked = false;
+    if (game.hasPossibleMove(players.first)) {
+      ponder(game);
+    }
   });
 });
 
+// Search the replies to the human's possible moves while the human is thinking, one bounded
+// step per event loop turn so that clicks are handled (and stop pondering) in between
+async function ponder(game: Reversi) {
+  game.startPondering();
+  while (!boardLocked && game.ponderStep()) {
+    await sleep(0);
+  }
+}
+
 const version = document.querySelector<HTMLElement>("#version");
 if (version) {
   version.textContent = process.env.REVERSI_VERSION ?? "";
 }
 
+// The level is given by `?level=N`, and changing it starts a new game
+function selectLevel(): number {
+  const params = new URLSearchParams(window.location.search);
+  const level = Number(params.get("level") ?? 5);
+  const validLevel = Number.isInteger(level) && level >= 1 && level <= 10 ? level : 5;
+
+  const select = document.querySelector<HTMLSelectElement>("#level");
+  if (select) {
+    select.value = String(validLevel);
+    select.addEventListener("change", () => {
+      params.set("level", select.value);
+      window.location.search = params.toString();
+    });
+  }
+  return validLevel;
+}
+
 const sleep = (milliSeconds: number) =>
   new Promise((resolve) => setTimeout(resolve, milliSeconds));
 
+// The opening book is optional: `cargo run book build ...` and put it in public/book.bin
+async function loadBook(game: Reversi) {
+  try {
+    const response = await fetch("./book.bin");
+    if (response.ok) {
+      game.loadBook(new Uint8Array(await response.arrayBuffer()), 1.0);
+    }
+  } catch (e) {
+    console.log(`opening book is not loaded: ${e}`);
+  }
+}
+
+function renderWinProbability(probability?: number) {
+  const scores = document.querySelector<HTMLElement>("#scores");
+  if (scores && probability !== undefined) {
+    scores.textContent = `Win probability: ${(100 * probability).toFixed(1)}%`;
+  }
+}
+
+function renderOpeningName(name?: string) {
+  const opening = document.querySelector<HTMLElement>("#opening");
+  if (opening) {
+    opening.textContent = name ?? "";
+  }
+}
+
 function draw(game: Reversi, r?: number, c?: number) {
   const first = game.getCurrentBitBoard(players.first);
   const second = game.getCurrentBitBoard(players.second);
   renderBoard(first, second, r, c);
+  renderOpeningName(game.getOpeningName());
 }
@@ -1,4 +1,4 @@
-import { Game, StrategyType, Player } from "reversi-wasm";
+import { Game, Player } from "reversi-wasm";
 
 export const players = { first: 0, second: 1 } as const;
 
@@ -6,13 +6,32 @@ type PlayerId = (typeof players)[keyof typeof players];
 
 type MoveResult = [boolean, number, number];
 
+// SearchResult of the engine, see engine/src/search_algorithm/result.rs
+export type SearchResult = {
+  best_move: string | null;
+  score: number;
+  bound: "exact" | "lower" | "upper";
+  principal_variation: (string | null)[];
+  depth: number;
+  n_nodes: number;
+  elapsed_ms: number;
+  source: "book" | "search" | "exact_solver" | "wld_solver" | "random";
+};
+
 export class Reversi {
   #game: Game;
 
-  constructor() {
+  // level: from 1 (beginner) to 10 (strongest), ignored when configJson (see StrategyConfig) is given
+  constructor(level: number, configJson?: string) {
     const player = Player.First;
-    const strategy = StrategyType.NumdiskLookahead;
-    this.#game = Game.create(player, strategy);
+    const seed = BigInt(Date.now());
+    this.#game =
+      configJson === undefined
+        ? Game.createWithLevel(player, level, seed)
+        : Game.createWithConfig(player, configJson, seed);
+  }
+  loadBook(bookBytes: Uint8Array, randomness: number): void {
+    this.#game.loadBook(bookBytes, randomness, BigInt(Date.now()));
   }
   getCurrentBitBoard(player: PlayerId): number[] {
     const board = this.#game.currentBoard();
@@ -29,6 +48,23 @@ export class Reversi {
     console.log(`putAndReverseOpponent returns invalid value: ${p}`);
     return [false, -1, -1];
   }
+  startPondering(): void {
+    this.#game.startPondering();
+  }
+  // returns false when there is nothing left to search
+  ponderStep(): boolean {
+    return this.#game.ponderStep();
+  }
+  getLastSearchResult(): SearchResult | undefined {
+    const json = this.#game.lastSearchResult();
+    return json === undefined ? undefined : JSON.parse(json);
+  }
+  getWinProbability(): number | undefined {
+    return this.#game.humanWinProbability();
+  }
+  getOpeningName(): string | undefined {
+    return this.#game.openingName();
+  }
   isPossibleMove(player: PlayerId, r: number, c: number): boolean {
     const legalPositions = this.#getCurrentAllLegalPosition(player);
     return legalPositions[c + 8 * r] > 0;
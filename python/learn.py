@@ -9,56 +9,46 @@ from sklearn.model_selection import train_test_split
 histories = np.loadtxt("./data/game-histories/02.csv", delimiter=",", dtype="i4")
 histories.shape
 
+# %%
+# パターンの定義は engine から出力する
+# cargo run pattern-set [patterns.txt] > ../python/data/pattern-set.csv
+df_pattern_set = pd.read_csv("./data/pattern-set.csv")
+n_transforms = df_pattern_set.n_transforms[0]
+n_columns = 3 + n_transforms * len(df_pattern_set)
+
 # %%
 # 序盤は定石データベースで処理するべきなので除外する
 # 終盤は完全読みするので除外する
 index = np.reshape(
-  np.repeat((histories[: ,1] > 10) & (histories[: ,1] < 50), 47),
-  (-1, 47)
+  np.repeat((histories[: ,1] > 10) & (histories[: ,1] < 50), n_columns),
+  (-1, n_columns)
 )
 histories = np.reshape(
   histories[index],
-  (-1, 47)
+  (-1, n_columns)
 )
 
 # %%
-pattern_ids = [i for i in range(11)]
-pattern_names = [
-  'diag4',
-  'diag5',
-  'diag6',
-  'diag7',
-  'diag8',
-
-  'hor./vert.2',
-  'hor./vert.3',
-  'hor./vert.4',
-
-  'edge+2X',
-  '2x5-corner',
-  '3x3-corner'
-]
-n_cells_each_pattern = [4, 5, 6, 7, 8, 8, 8, 8, 10, 10, 9]
+pattern_ids = df_pattern_set.pattern_id.values
+pattern_names = df_pattern_set.name.values
+n_cells_each_pattern = df_pattern_set.n_cells.values
 n_patterns = len(n_cells_each_pattern)
 xcol = (3 ** np.array(n_cells_each_pattern)).sum()
 
 # %%
 i1 = np.repeat(
   np.arange(histories.shape[0] * n_patterns),
-  4
+  n_transforms
 )
 i2_vstacked = histories[:, 3:]
-offsets = np.tile(
-  np.hstack([[0], (3 ** np.array(n_cells_each_pattern[:10])).cumsum()]),
-  4
-)
+offsets = np.tile(df_pattern_set.offset.values, n_transforms)
 i2 = np.ravel(i2_vstacked + offsets)
 
 X = coo_matrix(
   (np.ones_like(i1), (i1, i2)),
   shape=(i1.max() + 1, xcol)
 )
-y = np.repeat(histories[:, 2], 11)
+y = np.repeat(histories[:, 2], n_patterns)
 
 X_train, X_test, y_train, y_test_true = train_test_split(X, y, test_size=0.2, random_state=0, shuffle = False)
 